@@ -12,8 +12,8 @@ fn bench_compare_untracked(world: &World) {
 }
 
 fn bench_compare_tracked(world: &World, changes: &Changes) {
-    <(&mut u64, &mut u32)>::track(&changes)
-        .query(&world)
+    <(&mut u64, &mut u32)>::track(changes)
+        .query(world)
         .iter()
         .for_each(|(_, (a, b))| {
             let _ = *a == *b as u64;
@@ -28,12 +28,26 @@ fn bench_copy_untracked(world: &World) {
 }
 
 fn bench_copy_tracked(world: &World, changes: &Changes) {
-    <(&mut u64, &mut u32)>::track(&changes)
-        .query(&world)
+    <(&mut u64, &mut u32)>::track(changes)
+        .query(world)
         .iter()
         .for_each(|(_, (mut a, b))| *a = *b as u64);
 }
 
+fn bench_copy_untracked_mut(world: &mut World) {
+    world
+        .query_mut::<(&mut u64, &mut u32)>()
+        .into_iter()
+        .for_each(|(_, (a, b))| *a = *b as u64);
+}
+
+fn bench_copy_tracked_mut(world: &mut World, changes: &Changes) {
+    <(&mut u64, &mut u32)>::track(changes)
+        .query_mut(world)
+        .into_iter()
+        .for_each(|(_, (mut a, b))| *a = *b as u64);
+}
+
 pub fn tracked_vs_untracked(c: &mut Criterion) {
     let mut group = c.benchmark_group("Tracked vs untracked");
 
@@ -57,6 +71,13 @@ pub fn tracked_vs_untracked(c: &mut Criterion) {
     group.bench_function(BenchmarkId::new("Tracked read/write", count), |b| {
         b.iter(|| bench_copy_tracked(&world, &changes))
     });
+
+    group.bench_function(BenchmarkId::new("Untracked mut read/write", count), |b| {
+        b.iter(|| bench_copy_untracked_mut(&mut world))
+    });
+    group.bench_function(BenchmarkId::new("Tracked mut read/write", count), |b| {
+        b.iter(|| bench_copy_tracked_mut(&mut world, &changes))
+    });
 }
 
 criterion_group!(benches, tracked_vs_untracked);
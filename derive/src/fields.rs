@@ -0,0 +1,73 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, parse_quote, Data, DeriveInput, Error, Fields};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "TrackedFields can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "TrackedFields can only be derived for structs",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let trait_ident = format_ident!("{}Fields", ident);
+    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let field_names: Vec<_> = field_idents.iter().map(|f| f.unraw().to_string()).collect();
+    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let accessors: Vec<_> = field_idents
+        .iter()
+        .map(|f| format_ident!("{}_mut", f.unraw()))
+        .collect();
+
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let bound = parse_quote!(#ident #ty_generics: 'static);
+    let mut generics = input.generics.clone();
+    generics.make_where_clause().predicates.push(bound);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut tracked_generics = generics.clone();
+    tracked_generics.params.insert(0, parse_quote!('__tracked));
+    let (tracked_impl_generics, _, _) = tracked_generics.split_for_impl();
+
+    let trait_doc = format!("Field accessors of `TrackedMut<{}>`.", ident);
+
+    Ok(quote! {
+        impl #impl_generics ::hecs_query_tracker::TrackedFields for #ident #ty_generics #where_clause {
+            fn fields() -> &'static [&'static str] {
+                &[#(#field_names),*]
+            }
+        }
+
+        #[doc = #trait_doc]
+        #vis trait #trait_ident #impl_generics #where_clause {
+            #(
+                fn #accessors(&mut self) -> &mut #field_types;
+            )*
+        }
+
+        impl #tracked_impl_generics #trait_ident #ty_generics
+            for ::hecs_query_tracker::TrackedMut<'__tracked, #ident #ty_generics>
+        #where_clause
+        {
+            #(
+                #[inline]
+                fn #accessors(&mut self) -> &mut #field_types {
+                    self.field_mut(#field_names, |value| &mut value.#field_idents)
+                }
+            )*
+        }
+    })
+}
@@ -0,0 +1,27 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod fields;
+mod trackable;
+
+/// Implement `TrackedFields` for a struct with named fields
+/// and generate the `<Struct>Fields` trait with the `<field>_mut` accessors for `TrackedMut<Struct>`.
+#[proc_macro_derive(TrackedFields)]
+pub fn derive_tracked_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match fields::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Implement `TrackableRef` for a query struct with a single lifetime, i.e. one with `#[derive(hecs::Query)]`,
+/// and generate the `Tracked<Struct>` mirror struct with the tracked counterparts of its fields.
+#[proc_macro_derive(TrackableRef)]
+pub fn derive_trackable_ref(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match trackable::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,110 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, GenericParam, Index, Member};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "TrackableRef can only be derived for structs",
+            ))
+        }
+    };
+    let lifetime = match input.generics.params.iter().collect::<Vec<_>>().as_slice() {
+        [GenericParam::Lifetime(param)] => &param.lifetime,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.generics,
+                "TrackableRef can only be derived for structs with a single lifetime parameter",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let tracked_ident = format_ident!("Tracked{}", ident);
+    let members: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        })
+        .collect();
+    let slots: Vec<_> = (0..members.len())
+        .map(|i| format_ident!("slots_{}", i))
+        .collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let tracked_types: Vec<_> = types
+        .iter()
+        .map(|ty| quote!(<#ty as ::hecs_query_tracker::TrackableRef<#lifetime>>::Tracked))
+        .collect();
+    let slots_types: Vec<_> = types
+        .iter()
+        .map(|ty| quote!(<#ty as ::hecs_query_tracker::TrackableRef<#lifetime>>::Slots))
+        .collect();
+    let field_vis: Vec<_> = fields.iter().map(|f| &f.vis).collect();
+
+    let tracked_struct = match fields {
+        Fields::Named(_) => quote! {
+            #vis struct #tracked_ident<#lifetime> {
+                #(#field_vis #members: #tracked_types,)*
+            }
+        },
+        Fields::Unnamed(_) => quote! {
+            #vis struct #tracked_ident<#lifetime>(#(#field_vis #tracked_types,)*);
+        },
+        Fields::Unit => quote! {
+            #vis struct #tracked_ident<#lifetime>(::core::marker::PhantomData<&#lifetime ()>);
+        },
+    };
+    let tracked_value = match fields {
+        Fields::Unit => quote!(#tracked_ident(::core::marker::PhantomData)),
+        _ => quote! {
+            #tracked_ident {
+                #(#members: ::hecs_query_tracker::TrackableRef::into_tracked_with(self.#members, entity, #slots),)*
+            }
+        },
+    };
+    let doc = format!("Tracked counterpart of [`{}`].", ident);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #tracked_struct
+
+        impl<#lifetime> ::hecs_query_tracker::TrackableRef<#lifetime> for #ident<#lifetime> {
+            type Tracked = #tracked_ident<#lifetime>;
+            type Slots = (#(#slots_types,)*);
+
+            fn count_types() -> usize {
+                0 #(+ <#types as ::hecs_query_tracker::TrackableRef<#lifetime>>::count_types())*
+            }
+
+            fn for_each_type(mut f: impl FnMut(::core::any::TypeId, &'static str, bool)) {
+                #(<#types as ::hecs_query_tracker::TrackableRef<#lifetime>>::for_each_type(&mut f);)*
+                let _ = &mut f;
+            }
+
+            fn resolve_slots(
+                slots: &#lifetime [::hecs_query_tracker::ChangeSlot<#lifetime>],
+            ) -> Self::Slots {
+                #(
+                    let (#slots, slots) = slots.split_at(
+                        <#types as ::hecs_query_tracker::TrackableRef<#lifetime>>::count_types(),
+                    );
+                )*
+                let _ = slots;
+                (#(<#types as ::hecs_query_tracker::TrackableRef<#lifetime>>::resolve_slots(#slots),)*)
+            }
+
+            #[inline]
+            fn into_tracked_with(self, entity: ::hecs_query_tracker::hecs::Entity, slots: Self::Slots) -> Self::Tracked {
+                let (#(#slots,)*) = slots;
+                let _ = entity;
+                #tracked_value
+            }
+        }
+    })
+}
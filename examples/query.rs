@@ -1,4 +1,3 @@
-use core::any::{type_name, TypeId};
 use hecs::World;
 use hecs_query_tracker::{Changes, TrackableQuery};
 
@@ -13,11 +12,9 @@ fn main() {
         .iter()
         .for_each(|(_, (mut a, b))| *a = *b as i32);
 
-    for (type_id, type_name) in [
-        (TypeId::of::<i32>(), type_name::<i32>()),
-        (TypeId::of::<u32>(), type_name::<u32>()),
-    ] {
-        if changes.is_changed(type_id) {
+    for (_, type_name, changed) in changes.iter_named() {
+        let type_name = type_name.unwrap_or("unknown");
+        if changed {
             println!("{} is changed", type_name);
         } else {
             println!("{} is not changed", type_name);
@@ -1,95 +1,1270 @@
-use crate::TrackableRef;
+use crate::{ComponentSet, TrackableRef};
 use core::iter::{IntoIterator, Iterator};
 use core::{
-    any::TypeId,
-    sync::atomic::{AtomicBool, Ordering},
+    any::{type_name, Any, TypeId},
+    fmt,
+    hash::Hash,
+    ops::Deref,
+    sync::atomic::{AtomicU64, Ordering},
 };
-use std::collections::BTreeMap;
+use hecs::Entity;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard, OnceLock, RwLock};
 
+const BLOCK_SIZE: usize = u64::BITS as usize;
+const MAX_BLOCKS: usize = 64;
+/// Number of the first reserved types looked up without locking the index map.
+const SCANNED_TYPES: usize = 16;
+
+/// Entities with the tick of their last record.
+#[derive(Default)]
+struct EntityTicks(Mutex<BTreeMap<Entity, u64>>);
+
+impl EntityTicks {
+    fn insert(&self, entity: Entity, tick: u64) {
+        self.0.lock().unwrap().insert(entity, tick);
+    }
+
+    fn contains(&self, entity: Entity) -> bool {
+        self.0.lock().unwrap().contains_key(&entity)
+    }
+
+    fn contains_since(&self, entity: Entity, tick: u64) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&entity)
+            .is_some_and(|t| *t > tick)
+    }
+
+    fn for_each_since(&self, tick: u64, mut f: impl FnMut(Entity)) {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, t)| **t > tick)
+            .for_each(|(e, _)| f(*e))
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear()
+    }
+}
+
+type Observer = Box<dyn Fn(TypeId) + Send + Sync>;
+
+/// When the observers registered with [`Changes::observe`] are invoked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObserveMode {
+    /// By the thread that changed the type, the first time the flag is set.
+    Immediate,
+    /// By the next [`Changes::dispatch`] call.
+    Deferred,
+}
+
+struct TypeChanges {
+    type_id: TypeId,
+    type_name: OnceLock<&'static str>,
+    /// Tick of the last change, zero if the type was never changed.
+    tick: AtomicU64,
+    entities: EntityTicks,
+    added: EntityTicks,
+    removed: EntityTicks,
+    /// Changed fields with the tick of their last change.
+    fields: Mutex<BTreeMap<&'static str, u64>>,
+    entity_fields: Mutex<BTreeMap<(Entity, &'static str), u64>>,
+    /// Changed keys of a collection component, a `HashSet<(Entity, K)>`.
+    keys: Mutex<Option<Box<dyn Any + Send + Sync>>>,
+    observers: Vec<Observer>,
+    deferred_observers: Vec<Observer>,
+}
+
+impl TypeChanges {
+    fn new(type_id: TypeId) -> Self {
+        Self {
+            type_id,
+            type_name: OnceLock::new(),
+            tick: AtomicU64::new(0),
+            entities: EntityTicks::default(),
+            added: EntityTicks::default(),
+            removed: EntityTicks::default(),
+            fields: Mutex::new(BTreeMap::new()),
+            entity_fields: Mutex::new(BTreeMap::new()),
+            keys: Mutex::new(None),
+            observers: Vec::new(),
+            deferred_observers: Vec::new(),
+        }
+    }
+
+    /// Clear the recorded entities, fields and keys, the tick of the last change is kept.
+    fn clear_records(&self) {
+        self.entities.clear();
+        self.added.clear();
+        self.removed.clear();
+        self.fields.lock().unwrap().clear();
+        self.entity_fields.lock().unwrap().clear();
+        *self.keys.lock().unwrap() = None;
+    }
+}
+
+/// Changed flags of up to 64 consecutive types.
+struct Block {
+    flags: AtomicU64,
+    /// Types changed since the last [`Changes::dispatch`].
+    pending: AtomicU64,
+    /// Types borrowed mutably or reserved as interior-mutable.
+    mutable: AtomicU64,
+    /// Read-only types which were attempted to be changed in the strict mode.
+    rejected: AtomicU64,
+    types: [OnceLock<TypeChanges>; BLOCK_SIZE],
+}
+
+impl Block {
+    fn new() -> Self {
+        Self {
+            flags: AtomicU64::new(0),
+            pending: AtomicU64::new(0),
+            mutable: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            types: std::array::from_fn(|_| OnceLock::new()),
+        }
+    }
+}
+
+/// Changed flags of the component types.
+///
+/// Every reserved type gets a dense index and a bit in the flags bitset,
+/// so the tracked references can flag changes without any lookup.
+/// Blocks of the bitset are never moved or freed while [`Changes`] is borrowed,
+/// which allows to reserve new types through a shared reference, see [`Changes::with_auto_reserve`].
 pub struct Changes {
-    changes: BTreeMap<TypeId, AtomicBool>,
+    indices: RwLock<BTreeMap<TypeId, usize>>,
+    blocks: [OnceLock<Box<Block>>; MAX_BLOCKS],
+    tick: AtomicU64,
+    track_entities: bool,
+    track_fields: bool,
+    auto_reserve: bool,
+    strict: bool,
+    events: Option<Events>,
 }
 
-impl Changes {
-    pub fn new() -> Self {
+impl Default for Changes {
+    fn default() -> Self {
         Self {
-            changes: BTreeMap::new(),
+            indices: RwLock::new(BTreeMap::new()),
+            blocks: std::array::from_fn(|_| OnceLock::new()),
+            tick: AtomicU64::new(1),
+            track_entities: false,
+            track_fields: false,
+            auto_reserve: false,
+            strict: false,
+            events: None,
         }
     }
+}
+
+impl Changes {
+    /// Field name recorded when the whole value is mutated instead of a single field,
+    /// i.e. by dereferencing [`crate::TrackedMut`] mutably, see [`Changes::with_fields`].
+    pub const WHOLE_VALUE: &'static str = "*";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Reserve all types borrowed by `T` and remember which of them are borrowed mutably.
     pub fn new_for<'a, T: TrackableRef<'a>>() -> Self {
         let mut changes = Self::new();
-        T::for_each_type(|t, _| changes.reserve(t));
+        T::for_each_type(|t, n, m| {
+            let index = Self::insert(&changes.blocks, changes.indices.get_mut().unwrap(), t);
+            let slot = changes.reserved_slot(index);
+            slot.set_type_name(n);
+            if m {
+                slot.set_mutable();
+            }
+        });
         changes
     }
 
+    /// Enables recording of the entities whose components were mutated,
+    /// see [`Changes::changed_entities`].
+    pub fn with_entities(mut self) -> Self {
+        self.track_entities = true;
+        self
+    }
+
+    pub fn tracks_entities(&self) -> bool {
+        self.track_entities
+    }
+
+    /// Enables recording of the changed fields, see [`Changes::changed_fields`].
+    ///
+    /// Off by default, as every first mutation of a component records its field under a lock.
+    pub fn with_fields(mut self) -> Self {
+        self.track_fields = true;
+        self
+    }
+
+    pub fn tracks_fields(&self) -> bool {
+        self.track_fields
+    }
+
+    /// Enables the queue of `(Entity, TypeId)` change events, see [`Changes::drain_events`].
+    pub fn with_events(mut self) -> Self {
+        self.events = Some(Events::new());
+        self
+    }
+
+    pub fn records_events(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Take the queued change events in the order they were recorded.
+    ///
+    /// An event is pushed for the first mutation of a component by every tracked reference.
+    /// Events are queued until drained, so the consumer has to drain them regularly.
+    pub fn drain_events(&self) -> DrainEvents<'_> {
+        DrainEvents {
+            receiver: self
+                .events
+                .as_ref()
+                .map(|events| events.receiver.lock().unwrap()),
+        }
+    }
+
+    /// Reserve the missing types when the query is built instead of panicking on change.
+    pub fn with_auto_reserve(mut self) -> Self {
+        self.auto_reserve = true;
+        self
+    }
+
+    pub fn auto_reserves(&self) -> bool {
+        self.auto_reserve
+    }
+
+    /// Reject changes of the types that were never borrowed mutably, see [`Changes::rejected`].
+    ///
+    /// Types mutated through shared borrows have to be reserved with [`Changes::reserve_interior_mutable`].
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Current tick, changes made now are stamped with it.
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.tick.load(Ordering::Relaxed)
+    }
+
+    /// Start a new tick and return the previous one.
+    ///
+    /// Every consumer can keep the returned tick and pass it to [`Changes::changed_since`]
+    /// on its next run to see only the changes made after this call.
+    pub fn advance_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed)
+    }
+
     pub fn reserve(&mut self, type_id: TypeId) {
-        use std::collections::btree_map::Entry;
-        match self.changes.entry(type_id) {
-            Entry::Vacant(entry) => {
-                entry.insert(AtomicBool::new(false));
+        Self::insert(&self.blocks, self.indices.get_mut().unwrap(), type_id);
+    }
+
+    /// Same as [`Changes::reserve`] but also remembers the type name for debugging.
+    pub fn reserve_named(&mut self, type_id: TypeId, type_name: &'static str) {
+        let index = Self::insert(&self.blocks, self.indices.get_mut().unwrap(), type_id);
+        self.reserved_slot(index).set_type_name(type_name);
+    }
+
+    pub fn reserve_type<T: 'static>(&mut self) {
+        self.reserve_named(TypeId::of::<T>(), type_name::<T>())
+    }
+
+    /// Reserve `T` which is mutated through shared borrows, like `Cell` or atomics.
+    /// It is allowed to be changed in the strict mode even if it is never borrowed mutably.
+    pub fn reserve_interior_mutable<T: 'static>(&mut self) {
+        let index = Self::insert(
+            &self.blocks,
+            self.indices.get_mut().unwrap(),
+            TypeId::of::<T>(),
+        );
+        let slot = self.reserved_slot(index);
+        slot.set_type_name(type_name::<T>());
+        slot.set_mutable();
+    }
+
+    /// Whether the type was borrowed mutably by [`Changes::new_for`] or a tracked query,
+    /// or reserved as interior-mutable.
+    pub fn is_mutable(&self, type_id: TypeId) -> bool {
+        self.index_of(type_id).is_some_and(|index| {
+            let slot = self.reserved_slot(index);
+            slot.block.mutable.load(Ordering::Relaxed) & slot.mask != 0
+        })
+    }
+
+    /// Read-only types which were attempted to be changed in the strict mode, see [`Changes::with_strict`].
+    pub fn rejected(&self) -> BTreeSet<TypeId> {
+        let mut rejected = BTreeSet::new();
+        self.blocks
+            .iter()
+            .filter_map(|block| block.get())
+            .for_each(|block| {
+                let flags = block.rejected.load(Ordering::Relaxed);
+                block
+                    .types
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| flags & (1 << i) != 0)
+                    .filter_map(|(_, value)| value.get())
+                    .for_each(|value| {
+                        rejected.insert(value.type_id);
+                    })
+            });
+        rejected
+    }
+
+    /// Name of the reserved type, known if it was reserved by [`Changes::new_for`],
+    /// [`Changes::reserve_named`] or resolved by a tracked query.
+    pub fn type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.get(type_id)
+            .and_then(|value| value.type_name.get().copied())
+    }
+
+    /// Check that every type borrowed by `T` is reserved.
+    /// Shared borrows are checked too, as they may be changed through interior mutability.
+    pub fn check_reserved<'a, T: TrackableRef<'a>>(&self) -> Result<(), NotReserved> {
+        if self.auto_reserve {
+            return Ok(());
+        }
+        let indices = self.indices.read().unwrap();
+        let mut type_names = vec![];
+        T::for_each_type(|t, name, _| {
+            if !indices.contains_key(&t) && !type_names.contains(&name) {
+                type_names.push(name);
             }
-            Entry::Occupied(_) => (),
+        });
+        if type_names.is_empty() {
+            Ok(())
+        } else {
+            Err(NotReserved { type_names })
         }
     }
 
+    /// Resolve the slots of all types borrowed by `T` in the order of [`TrackableRef::for_each_type`].
+    pub fn slots_for<'a, T: TrackableRef<'a>>(&'a self) -> ChangeSlots<'a> {
+        let unreserved = ChangeSlot {
+            changes: self,
+            reserved: None,
+        };
+        let mut slots = ChangeSlots::filled(T::count_types(), unreserved);
+        let mut index = 0;
+        let slots_mut = slots.as_mut_slice();
+        T::for_each_type(|t, n, m| {
+            slots_mut[index] = self.slot(t);
+            if let Some(reserved) = slots_mut[index].reserved {
+                reserved.set_type_name(n);
+                if m {
+                    reserved.set_mutable();
+                }
+            }
+            index += 1;
+        });
+        slots
+    }
+
+    /// Resolve the slot of `type_id`.
+    /// Setting a slot of a type that is not reserved will panic, so do the mutable accesses
+    /// of tracked queries and references, unless the types are reserved with [`Changes::with_auto_reserve`].
+    /// Only the structural changes recorded by [`crate::TrackedWorld`] skip the unreserved types.
+    #[inline]
+    pub fn slot(&self, type_id: TypeId) -> ChangeSlot<'_> {
+        let index = if self.auto_reserve {
+            Some(self.reserve_shared(type_id))
+        } else {
+            self.index_of(type_id)
+        };
+        ChangeSlot {
+            changes: self,
+            reserved: index.map(|index| self.reserved_slot(index)),
+        }
+    }
+
+    /// Clear all changed flags and recorded entities, including the added and removed ones.
+    /// Ticks of the last change of each type are kept.
     pub fn reset(&mut self) {
-        self.changes
+        self.blocks
             .iter_mut()
-            .for_each(|(_, v)| v.store(false, Ordering::Relaxed));
+            .filter_map(|block| block.get_mut())
+            .for_each(|block| {
+                *block.flags.get_mut() = 0;
+                *block.pending.get_mut() = 0;
+                *block.rejected.get_mut() = 0;
+                block
+                    .types
+                    .iter()
+                    .filter_map(|value| value.get())
+                    .for_each(|value| value.clear_records());
+            });
+    }
+
+    /// Invoke `f` with `type_id` when the type becomes changed, see [`ObserveMode`].
+    /// The flag has to be cleared by [`Changes::reset`] or [`Changes::take_changed`]
+    /// for the observers to fire again.
+    pub fn observe(
+        &mut self,
+        type_id: TypeId,
+        mode: ObserveMode,
+        f: impl Fn(TypeId) + Send + Sync + 'static,
+    ) {
+        self.reserve(type_id);
+        let index = self.indices.get_mut().unwrap()[&type_id];
+        let value = self.blocks[index / BLOCK_SIZE]
+            .get_mut()
+            .and_then(|block| block.types[index % BLOCK_SIZE].get_mut())
+            .expect("Reserved type index is out of bounds");
+        match mode {
+            ObserveMode::Immediate => value.observers.push(Box::new(f)),
+            ObserveMode::Deferred => value.deferred_observers.push(Box::new(f)),
+        }
+    }
+
+    pub fn observe_type<T: 'static>(
+        &mut self,
+        mode: ObserveMode,
+        f: impl Fn(TypeId) + Send + Sync + 'static,
+    ) {
+        self.reserve_type::<T>();
+        self.observe(TypeId::of::<T>(), mode, f)
+    }
+
+    /// Invoke the deferred observers of the types changed since the previous call.
+    pub fn dispatch(&self) {
+        self.blocks
+            .iter()
+            .filter_map(|block| block.get())
+            .for_each(|block| {
+                let pending = block.pending.swap(0, Ordering::Relaxed);
+                block
+                    .types
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| pending & (1 << i) != 0)
+                    .filter_map(|(_, value)| value.get())
+                    .for_each(|value| {
+                        value
+                            .deferred_observers
+                            .iter()
+                            .for_each(|f| f(value.type_id))
+                    });
+            });
+    }
+
+    pub fn is_any_changed(&self, type_ids: &[TypeId]) -> bool {
+        type_ids.iter().any(|t| self.is_changed(*t))
+    }
+
+    /// Changed types among the components of `C`, i.e. `changes.changed_among::<(A, B, C)>()`.
+    pub fn changed_among<C: ComponentSet>(&self) -> Vec<TypeId> {
+        let mut changed = vec![];
+        C::for_each_type(|t| {
+            if self.is_changed(t) {
+                changed.push(t)
+            }
+        });
+        changed
+    }
+
+    /// Types changed in `self` or `other`.
+    pub fn union(&self, other: &Changes) -> BTreeSet<TypeId> {
+        let mut changed = BTreeSet::new();
+        self.for_each_changed(|t| {
+            changed.insert(t);
+        });
+        other.for_each_changed(|t| {
+            changed.insert(t);
+        });
+        changed
+    }
+
+    /// Types changed in both `self` and `other`.
+    pub fn intersection(&self, other: &Changes) -> BTreeSet<TypeId> {
+        let mut changed = BTreeSet::new();
+        self.for_each_changed(|t| {
+            if other.is_changed(t) {
+                changed.insert(t);
+            }
+        });
+        changed
+    }
+
+    /// Types changed in `self` but not in `other`.
+    pub fn difference(&self, other: &Changes) -> BTreeSet<TypeId> {
+        let mut changed = BTreeSet::new();
+        self.for_each_changed(|t| {
+            if !other.is_changed(t) {
+                changed.insert(t);
+            }
+        });
+        changed
+    }
+
+    /// Record the changes of `self` into `other`, reserving the missing types.
+    ///
+    /// Changed, added and removed entities are recorded with the current tick of `other`.
+    /// Only the types changed in `self` are flagged, no change events are queued for the entities.
+    /// The types mutable in `self` become mutable in `other`, see [`Changes::with_strict`].
+    pub fn merge_into(&self, other: &mut Changes) {
+        self.iter_named().for_each(|(t, n, _)| {
+            match n {
+                Some(n) => other.reserve_named(t, n),
+                None => other.reserve(t),
+            }
+            if self.is_mutable(t) {
+                other.slot(t).set_mutable();
+            }
+        });
+        let tick = other.tick();
+        self.iter().for_each(|(t, changed)| {
+            let value = self.get(t).expect("Iterated type is not reserved");
+            if changed {
+                // Already accepted by `self`, so not rejected again by a strict `other`.
+                other.slot(t).flag_unchecked();
+            }
+            let target = other.get(t).expect("Merged type is not reserved");
+            value
+                .entities
+                .for_each_since(0, |e| target.entities.insert(e, tick));
+            value
+                .added
+                .for_each_since(0, |e| target.added.insert(e, tick));
+            value
+                .removed
+                .for_each_since(0, |e| target.removed.insert(e, tick));
+        });
+    }
+
+    /// Atomically clear all changed flags and return the types that were changed.
+    ///
+    /// Unlike [`Changes::reset`] this works through a shared reference,
+    /// a flag set concurrently is either returned now or kept for the next call.
+    ///
+    /// Recorded entities, fields, keys, added and removed components of all types are cleared too,
+    /// so they have to be read before the call. A record made concurrently is either cleared now
+    /// or kept for the next call, even if its flag is returned now.
+    ///
+    /// Ticks of the last change of the types are kept, but the ticks of the recorded entities are cleared
+    /// with them, so [`Changes::is_entity_changed_since`] only sees the entities changed after this call.
+    /// Consumers relying on the entity ticks should use [`Changes::advance_tick`] without taking the changes.
+    pub fn take_changed(&self) -> BTreeSet<TypeId> {
+        let mut changed = BTreeSet::new();
+        self.blocks
+            .iter()
+            .filter_map(|block| block.get())
+            .for_each(|block| {
+                // Records first, the changes flag their type before recording, so no record is lost.
+                block
+                    .types
+                    .iter()
+                    .filter_map(|value| value.get())
+                    .for_each(|value| value.clear_records());
+                let flags = block.flags.swap(0, Ordering::Relaxed);
+                block
+                    .types
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| flags & (1 << i) != 0)
+                    .filter_map(|(_, value)| value.get())
+                    .for_each(|value| {
+                        changed.insert(value.type_id);
+                    });
+            });
+        changed
     }
 
     pub fn for_each_changed(&self, mut f: impl FnMut(TypeId)) {
-        self.changes.iter().for_each(|(t, c)| {
-            if c.load(Ordering::Relaxed) {
-                f(*t)
+        self.iter().for_each(|(t, changed)| {
+            if changed {
+                f(t)
             }
         })
     }
 
-    pub fn set_changed(&self, type_id: TypeId) {
-        if let Some(value) = self.changes.get(&type_id) {
-            value.store(true, Ordering::Relaxed);
-        } else {
-            panic!("Changed flag for type_id is not reserved");
+    /// Invoke `f` for every entity whose component of type `type_id` was mutated.
+    /// Entities are only recorded if the entity tracking is enabled with [`Changes::with_entities`].
+    pub fn for_each_changed_entity(&self, type_id: TypeId, f: impl FnMut(Entity)) {
+        self.for_each_changed_entity_since(type_id, 0, f)
+    }
+
+    /// Invoke `f` for every entity whose component of type `type_id` was mutated after `tick`.
+    pub fn for_each_changed_entity_since(&self, type_id: TypeId, tick: u64, f: impl FnMut(Entity)) {
+        if let Some(value) = self.get(type_id) {
+            value.entities.for_each_since(tick, f)
+        }
+    }
+
+    /// Invoke `f` for every entity that got a component of type `type_id`,
+    /// see [`crate::TrackedWorld`].
+    pub fn for_each_added(&self, type_id: TypeId, f: impl FnMut(Entity)) {
+        if let Some(value) = self.get(type_id) {
+            value.added.for_each_since(0, f)
         }
     }
 
+    /// Invoke `f` for every entity that lost a component of type `type_id`,
+    /// see [`crate::TrackedWorld`].
+    pub fn for_each_removed(&self, type_id: TypeId, f: impl FnMut(Entity)) {
+        if let Some(value) = self.get(type_id) {
+            value.removed.for_each_since(0, f)
+        }
+    }
+
+    pub fn added_entities<T: 'static>(&self) -> Vec<Entity> {
+        let mut entities = vec![];
+        self.for_each_added(TypeId::of::<T>(), |e| entities.push(e));
+        entities
+    }
+
+    pub fn removed_entities<T: 'static>(&self) -> Vec<Entity> {
+        let mut entities = vec![];
+        self.for_each_removed(TypeId::of::<T>(), |e| entities.push(e));
+        entities
+    }
+
+    pub fn is_entity_added(&self, type_id: TypeId, entity: Entity) -> bool {
+        self.get(type_id)
+            .is_some_and(|value| value.added.contains(entity))
+    }
+
+    pub fn is_entity_removed(&self, type_id: TypeId, entity: Entity) -> bool {
+        self.get(type_id)
+            .is_some_and(|value| value.removed.contains(entity))
+    }
+
+    /// Invoke `f` for every changed field of `type_id`, see [`ChangeSlot::set_field_changed`].
+    pub fn for_each_changed_field(&self, type_id: TypeId, mut f: impl FnMut(&'static str)) {
+        if let Some(value) = self.get(type_id) {
+            value.fields.lock().unwrap().keys().for_each(|n| f(n))
+        }
+    }
+
+    /// Invoke `f` for every changed field of `type_id` of `entity`.
+    /// Entities are only recorded if the entity tracking is enabled with [`Changes::with_entities`].
+    pub fn for_each_changed_entity_field(
+        &self,
+        type_id: TypeId,
+        entity: Entity,
+        mut f: impl FnMut(&'static str),
+    ) {
+        if let Some(value) = self.get(type_id) {
+            value
+                .entity_fields
+                .lock()
+                .unwrap()
+                .range((entity, "")..)
+                .take_while(|((e, _), _)| *e == entity)
+                .for_each(|((_, n), _)| f(n))
+        }
+    }
+
+    /// Changed fields of `T`, including [`Changes::WHOLE_VALUE`] if the whole value was mutated.
+    /// Fields are only recorded if enabled with [`Changes::with_fields`].
+    pub fn changed_fields<T: 'static>(&self) -> Vec<&'static str> {
+        let mut fields = vec![];
+        self.for_each_changed_field(TypeId::of::<T>(), |n| fields.push(n));
+        fields
+    }
+
+    pub fn changed_entity_fields<T: 'static>(&self, entity: Entity) -> Vec<&'static str> {
+        let mut fields = vec![];
+        self.for_each_changed_entity_field(TypeId::of::<T>(), entity, |n| fields.push(n));
+        fields
+    }
+
+    /// Invoke `f` for every changed key of `type_id` and the entity it was changed on,
+    /// see [`ChangeSlot::set_key_changed`]. Keys are reported in no particular order.
+    /// Nothing is reported if the keys were recorded with another key type.
+    pub fn for_each_changed_key<K: 'static>(&self, type_id: TypeId, mut f: impl FnMut(Entity, &K)) {
+        if let Some(value) = self.get(type_id) {
+            let keys = value.keys.lock().unwrap();
+            if let Some(keys) = keys
+                .as_ref()
+                .and_then(|keys| keys.downcast_ref::<HashSet<(Entity, K)>>())
+            {
+                keys.iter().for_each(|(e, k)| f(*e, k))
+            }
+        }
+    }
+
+    /// Changed keys or indices of the collection component `T`, see [`crate::ElementMut`].
+    pub fn changed_keys<T: 'static, K: Clone + 'static>(&self) -> Vec<(Entity, K)> {
+        let mut keys = vec![];
+        self.for_each_changed_key(TypeId::of::<T>(), |e, k: &K| keys.push((e, k.clone())));
+        keys
+    }
+
+    pub fn changed_entity_keys<T: 'static, K: Clone + 'static>(&self, entity: Entity) -> Vec<K> {
+        let mut keys = vec![];
+        self.for_each_changed_key(TypeId::of::<T>(), |e, k: &K| {
+            if e == entity {
+                keys.push(k.clone())
+            }
+        });
+        keys
+    }
+
+    pub fn changed_entities<T: 'static>(&self) -> Vec<Entity> {
+        let mut entities = vec![];
+        self.for_each_changed_entity(TypeId::of::<T>(), |e| entities.push(e));
+        entities
+    }
+
+    pub fn set_changed(&self, type_id: TypeId) {
+        self.slot(type_id).set_changed()
+    }
+
+    /// Same as [`Changes::set_changed`] but also records `entity` if the entity tracking is enabled.
+    pub fn set_entity_changed(&self, type_id: TypeId, entity: Entity) {
+        self.slot(type_id).set_entity_changed(entity)
+    }
+
     pub fn is_changed(&self, type_id: TypeId) -> bool {
-        match self.changes.get(&type_id) {
-            Some(value) => value.load(Ordering::Relaxed),
+        match self.index_of(type_id) {
+            Some(index) => self.reserved_slot(index).is_changed(),
+            None => false,
+        }
+    }
+
+    pub fn is_entity_changed(&self, type_id: TypeId, entity: Entity) -> bool {
+        match self.get(type_id) {
+            Some(value) => value.entities.contains(entity),
+            None => false,
+        }
+    }
+
+    /// Tick of the last change of `type_id`, `None` if it was never changed.
+    pub fn changed_tick(&self, type_id: TypeId) -> Option<u64> {
+        match self.get(type_id) {
+            Some(value) => match value.tick.load(Ordering::Relaxed) {
+                0 => None,
+                tick => Some(tick),
+            },
+            None => None,
+        }
+    }
+
+    /// Whether `type_id` was changed after `tick`, see [`Changes::advance_tick`].
+    pub fn changed_since(&self, type_id: TypeId, tick: u64) -> bool {
+        self.changed_tick(type_id).is_some_and(|t| t > tick)
+    }
+
+    /// Whether `entity` was changed after `tick`, the entity ticks are cleared
+    /// by [`Changes::reset`] and [`Changes::take_changed`] with the recorded entities.
+    pub fn is_entity_changed_since(&self, type_id: TypeId, entity: Entity, tick: u64) -> bool {
+        match self.get(type_id) {
+            Some(value) => value.entities.contains_since(entity, tick),
             None => false,
         }
     }
 
     pub fn iter(&self) -> ChangesIter<'_> {
-        ChangesIter::new(self.changes.iter())
+        ChangesIter::new(self)
+    }
+
+    /// Same as [`Changes::iter`] but also yields the type names, see [`Changes::type_name`].
+    pub fn iter_named(&self) -> NamedChangesIter<'_> {
+        NamedChangesIter::new(self)
+    }
+
+    fn insert(
+        blocks: &[OnceLock<Box<Block>>; MAX_BLOCKS],
+        indices: &mut BTreeMap<TypeId, usize>,
+        type_id: TypeId,
+    ) -> usize {
+        if let Some(index) = indices.get(&type_id) {
+            return *index;
+        }
+        let index = indices.len();
+        assert!(
+            index < BLOCK_SIZE * MAX_BLOCKS,
+            "Too many types reserved for changes tracking"
+        );
+        let block = blocks[index / BLOCK_SIZE].get_or_init(|| Box::new(Block::new()));
+        let _ = block.types[index % BLOCK_SIZE].set(TypeChanges::new(type_id));
+        indices.insert(type_id, index);
+        index
+    }
+
+    fn reserve_shared(&self, type_id: TypeId) -> usize {
+        match self.index_of(type_id) {
+            Some(index) => index,
+            None => Self::insert(&self.blocks, &mut self.indices.write().unwrap(), type_id),
+        }
     }
+
+    #[inline]
+    fn index_of(&self, type_id: TypeId) -> Option<usize> {
+        // The types are stored in the order of their indices, so the first ones are found without locking.
+        for index in 0..SCANNED_TYPES {
+            match self.entry(index) {
+                Some(slot) if slot.value.type_id == type_id => return Some(index),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+        self.indices.read().unwrap().get(&type_id).copied()
+    }
+
+    fn get(&self, type_id: TypeId) -> Option<&TypeChanges> {
+        self.index_of(type_id)
+            .map(|index| self.reserved_slot(index).value)
+    }
+
+    #[inline]
+    fn entry(&self, index: usize) -> Option<ReservedSlot<'_>> {
+        let block = self.blocks.get(index / BLOCK_SIZE)?.get()?;
+        Some(ReservedSlot {
+            block,
+            mask: 1 << (index % BLOCK_SIZE),
+            value: block.types[index % BLOCK_SIZE].get()?,
+        })
+    }
+
+    #[inline]
+    fn reserved_slot(&self, index: usize) -> ReservedSlot<'_> {
+        self.entry(index)
+            .expect("Reserved type index is out of bounds")
+    }
+}
+
+/// Change events queue, pushing does not block.
+struct Events {
+    sender: Sender<(Entity, TypeId)>,
+    receiver: Mutex<Receiver<(Entity, TypeId)>>,
 }
 
-type ChangesInnerIter<'a> = std::collections::btree_map::Iter<'a, TypeId, AtomicBool>;
+impl Events {
+    fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+/// Iterator over the queued change events, see [`Changes::drain_events`].
+pub struct DrainEvents<'a> {
+    receiver: Option<MutexGuard<'a, Receiver<(Entity, TypeId)>>>,
+}
+
+impl<'a> Iterator for DrainEvents<'a> {
+    type Item = (Entity, TypeId);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.as_ref()?.try_recv().ok()
+    }
+}
+
+/// Error returned when types borrowed by a query are not reserved in [`Changes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotReserved {
+    type_names: Vec<&'static str>,
+}
+
+impl NotReserved {
+    pub fn type_names(&self) -> &[&'static str] {
+        &self.type_names
+    }
+}
+
+impl fmt::Display for NotReserved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Changed flags are not reserved for: {}",
+            self.type_names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NotReserved {}
+
+#[derive(Clone, Copy)]
+struct ReservedSlot<'a> {
+    block: &'a Block,
+    mask: u64,
+    value: &'a TypeChanges,
+}
+
+impl<'a> ReservedSlot<'a> {
+    #[inline]
+    fn is_changed(&self) -> bool {
+        self.block.flags.load(Ordering::Relaxed) & self.mask != 0
+    }
+
+    fn reject(&self) {
+        self.block.rejected.fetch_or(self.mask, Ordering::Relaxed);
+    }
+
+    /// Set the changed flag and notify the immediate observers if it was not set yet.
+    fn set_changed(&self) {
+        let flags = self.block.flags.fetch_or(self.mask, Ordering::Relaxed);
+        if flags & self.mask == 0 {
+            self.block.pending.fetch_or(self.mask, Ordering::Relaxed);
+            self.value
+                .observers
+                .iter()
+                .for_each(|f| f(self.value.type_id));
+        }
+    }
+
+    #[inline]
+    fn set_type_name(&self, type_name: &'static str) {
+        if self.value.type_name.get().is_none() {
+            let _ = self.value.type_name.set(type_name);
+        }
+    }
+
+    #[inline]
+    fn set_mutable(&self) {
+        // Plain load first, the types are resolved as mutable by every query.
+        if self.block.mutable.load(Ordering::Relaxed) & self.mask == 0 {
+            self.block.mutable.fetch_or(self.mask, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Number of slots stored without allocating by [`ChangeSlots`].
+const INLINE_SLOTS: usize = 4;
+
+/// Slots of the borrowed types of a query, see [`Changes::slots_for`].
+/// Small queries keep them inline so that resolving them does not allocate.
+#[derive(Clone)]
+pub struct ChangeSlots<'a>(SlotsStorage<'a>);
+
+#[derive(Clone)]
+enum SlotsStorage<'a> {
+    Inline(usize, [ChangeSlot<'a>; INLINE_SLOTS]),
+    Heap(Vec<ChangeSlot<'a>>),
+    Borrowed(&'a [ChangeSlot<'a>]),
+}
+
+impl<'a> ChangeSlots<'a> {
+    #[inline]
+    fn filled(count: usize, slot: ChangeSlot<'a>) -> Self {
+        if count <= INLINE_SLOTS {
+            Self(SlotsStorage::Inline(count, [slot; INLINE_SLOTS]))
+        } else {
+            Self(SlotsStorage::Heap(vec![slot; count]))
+        }
+    }
+
+    /// Borrow the slots without copying them, used by the queries of a [`crate::TrackedQueryBuilder`].
+    #[inline]
+    pub(crate) fn borrowed(&self) -> ChangeSlots<'_> {
+        ChangeSlots(SlotsStorage::Borrowed(self))
+    }
+
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [ChangeSlot<'a>] {
+        match &mut self.0 {
+            SlotsStorage::Inline(count, slots) => &mut slots[..*count],
+            SlotsStorage::Heap(slots) => slots,
+            SlotsStorage::Borrowed(_) => unreachable!("Borrowed slots are not resolved"),
+        }
+    }
+}
+
+impl<'a> Deref for ChangeSlots<'a> {
+    type Target = [ChangeSlot<'a>];
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match &self.0 {
+            SlotsStorage::Inline(count, slots) => &slots[..*count],
+            SlotsStorage::Heap(slots) => slots,
+            SlotsStorage::Borrowed(slots) => slots,
+        }
+    }
+}
+
+/// Location of a single type in [`Changes`], resolved once per query.
+#[derive(Clone, Copy)]
+pub struct ChangeSlot<'a> {
+    changes: &'a Changes,
+    reserved: Option<ReservedSlot<'a>>,
+}
+
+impl<'a> ChangeSlot<'a> {
+    pub fn changes(&self) -> &'a Changes {
+        self.changes
+    }
+
+    pub fn is_reserved(&self) -> bool {
+        self.reserved.is_some()
+    }
+
+    pub fn is_changed(&self) -> bool {
+        match self.reserved {
+            Some(slot) => slot.is_changed(),
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn set_changed(&self) {
+        self.flag();
+    }
+
+    /// Whether changes are allowed, only the mutable types can be changed in the strict mode.
+    #[inline]
+    fn is_allowed(&self) -> bool {
+        let slot = self.get_reserved();
+        !self.changes.strict || slot.block.mutable.load(Ordering::Relaxed) & slot.mask != 0
+    }
+
+    #[inline]
+    pub(crate) fn set_mutable(&self) {
+        self.get_reserved().set_mutable();
+    }
+
+    #[inline]
+    pub(crate) fn set_type_name(&self, type_name: &'static str) {
+        self.get_reserved().set_type_name(type_name);
+    }
+
+    /// Set the changed flag, returns `false` if the change is rejected in the strict mode.
+    // Forced with its callers down to `set_value_changed_once`, so that the first mutable access
+    // of every tracked reference is only a few loads, the rare updates are not inlined.
+    #[inline(always)]
+    fn flag(&self) -> bool {
+        let slot = self.get_reserved();
+        if !self.is_allowed() {
+            slot.reject();
+            return false;
+        }
+        self.flag_unchecked();
+        true
+    }
+
+    /// Same as [`ChangeSlot::flag`] without the strict mode check.
+    #[inline(always)]
+    fn flag_unchecked(&self) {
+        let slot = self.get_reserved();
+        // Plain load first to keep the cache line shared while the flag is already set.
+        if !slot.is_changed() {
+            slot.set_changed();
+        }
+        // Same for the tick, which only changes once per tick.
+        let tick = self.changes.tick();
+        if slot.value.tick.load(Ordering::Relaxed) != tick {
+            slot.value.tick.store(tick, Ordering::Relaxed);
+        }
+    }
+
+    /// Same as [`ChangeSlot::set_changed`] but also records `entity` if the entity tracking is enabled.
+    #[inline]
+    pub fn set_entity_changed(&self, entity: Entity) {
+        self.flag_entity(entity);
+    }
+
+    #[inline(always)]
+    fn flag_entity(&self, entity: Entity) -> bool {
+        if !self.flag() {
+            return false;
+        }
+        if self.changes.track_entities || self.changes.events.is_some() {
+            self.record_entity(entity);
+        }
+        true
+    }
+
+    fn record_entity(&self, entity: Entity) {
+        if self.changes.track_entities {
+            let tick = self.changes.tick();
+            self.get_reserved().value.entities.insert(entity, tick);
+        }
+        if let Some(events) = &self.changes.events {
+            let _ = events
+                .sender
+                .send((entity, self.get_reserved().value.type_id));
+        }
+    }
+
+    /// Record that `entity` got a component of this type.
+    pub fn set_added(&self, entity: Entity) {
+        let tick = self.changes.tick();
+        self.get_reserved().value.added.insert(entity, tick);
+    }
+
+    /// Record that `entity` lost a component of this type.
+    pub fn set_removed(&self, entity: Entity) {
+        let tick = self.changes.tick();
+        self.get_reserved().value.removed.insert(entity, tick);
+    }
+
+    /// Same as [`ChangeSlot::set_entity_changed`] but also records the changed `field`,
+    /// if enabled with [`Changes::with_fields`].
+    #[inline(always)]
+    pub fn set_field_changed(&self, entity: Entity, field: &'static str) {
+        if self.flag_entity(entity) {
+            self.record_field(entity, field);
+        }
+    }
+
+    /// Same as [`ChangeSlot::set_field_changed`] unless `flagged` is already set,
+    /// then the field is only recorded so the change events are not repeated.
+    #[inline(always)]
+    pub(crate) fn set_field_changed_once(
+        &self,
+        entity: Entity,
+        field: &'static str,
+        flagged: &mut bool,
+    ) {
+        if core::mem::replace(flagged, true) {
+            self.record_field(entity, field);
+        } else {
+            self.set_field_changed(entity, field);
+        }
+    }
+
+    /// Same as [`ChangeSlot::set_field_changed`] with [`Changes::WHOLE_VALUE`].
+    #[inline]
+    pub(crate) fn set_value_changed(&self, entity: Entity) {
+        self.set_field_changed(entity, Changes::WHOLE_VALUE)
+    }
+
+    /// Same as [`ChangeSlot::set_value_changed`] unless `flagged` is already set,
+    /// then the whole value is only recorded so the change events are not repeated.
+    #[inline(always)]
+    pub(crate) fn set_value_changed_once(&self, entity: Entity, flagged: &mut bool) {
+        self.set_field_changed_once(entity, Changes::WHOLE_VALUE, flagged);
+    }
+
+    /// Record the changed `field` of the type that is already flagged.
+    #[inline]
+    pub(crate) fn record_field(&self, entity: Entity, field: &'static str) {
+        if self.changes.track_fields && self.is_allowed() {
+            self.insert_field(entity, field);
+        }
+    }
+
+    fn insert_field(&self, entity: Entity, field: &'static str) {
+        let tick = self.changes.tick();
+        let value = self.get_reserved().value;
+        value.fields.lock().unwrap().insert(field, tick);
+        if self.changes.track_entities {
+            value
+                .entity_fields
+                .lock()
+                .unwrap()
+                .insert((entity, field), tick);
+        }
+    }
+
+    /// Same as [`ChangeSlot::set_entity_changed`] but also records the changed `key` of a collection component.
+    /// All keys of a type have to be of the same type `K`.
+    pub fn set_key_changed<K>(&self, entity: Entity, key: K)
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+    {
+        if self.flag_entity(entity) {
+            self.record_keys(entity, [key]);
+        }
+    }
+
+    /// Record the changed `keys` of the collection component of `entity` which is already flagged.
+    pub(crate) fn record_keys<K>(&self, entity: Entity, keys: impl IntoIterator<Item = K>)
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+    {
+        if !self.is_allowed() {
+            return;
+        }
+        let mut all_keys = self.get_reserved().value.keys.lock().unwrap();
+        let all_keys = all_keys
+            .get_or_insert_with(|| Box::new(HashSet::<(Entity, K)>::new()))
+            .downcast_mut::<HashSet<(Entity, K)>>()
+            .expect("Changed keys are recorded with another key type");
+        keys.into_iter().for_each(|key| {
+            all_keys.insert((entity, key));
+        });
+    }
+
+    #[inline]
+    fn get_reserved(&self) -> ReservedSlot<'a> {
+        match self.reserved {
+            Some(slot) => slot,
+            None => panic!("Changed flag for type_id is not reserved"),
+        }
+    }
+}
 
 pub struct ChangesIter<'a> {
-    inner: ChangesInnerIter<'a>,
+    changes: &'a Changes,
+    index: usize,
 }
 
 impl<'a> ChangesIter<'a> {
-    fn new(inner: ChangesInnerIter<'a>) -> ChangesIter {
-        Self { inner }
+    fn new(changes: &'a Changes) -> ChangesIter<'a> {
+        Self { changes, index: 0 }
     }
 }
 
 impl<'a> Iterator for ChangesIter<'a> {
     type Item = (TypeId, bool);
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.inner.next();
-        if let Some((type_id, atomic)) = next {
-            Some((type_id.clone(), atomic.load(Ordering::Relaxed)))
-        } else {
-            None
+        let slot = self.changes.entry(self.index)?;
+        self.index += 1;
+        Some((slot.value.type_id, slot.is_changed()))
+    }
+}
+
+pub struct NamedChangesIter<'a> {
+    inner: ChangesIter<'a>,
+}
+
+impl<'a> NamedChangesIter<'a> {
+    fn new(changes: &'a Changes) -> NamedChangesIter<'a> {
+        Self {
+            inner: ChangesIter::new(changes),
         }
     }
 }
 
+impl<'a> Iterator for NamedChangesIter<'a> {
+    type Item = (TypeId, Option<&'static str>, bool);
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.inner.changes.entry(self.inner.index)?;
+        self.inner.index += 1;
+        Some((
+            slot.value.type_id,
+            slot.value.type_name.get().copied(),
+            slot.is_changed(),
+        ))
+    }
+}
+
+impl fmt::Debug for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Name(TypeId, Option<&'static str>);
+        impl fmt::Debug for Name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.1 {
+                    Some(name) => f.write_str(name),
+                    None => self.0.fmt(f),
+                }
+            }
+        }
+        f.debug_map()
+            .entries(
+                self.iter_named()
+                    .map(|(type_id, name, changed)| (Name(type_id, name), changed)),
+            )
+            .finish()
+    }
+}
+
 impl<'a> IntoIterator for &'a Changes {
     type Item = (TypeId, bool);
     type IntoIter = ChangesIter<'a>;
@@ -97,3 +1272,405 @@ impl<'a> IntoIterator for &'a Changes {
         self.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Changes;
+    use core::any::TypeId;
+    use hecs::World;
+
+    #[test]
+    fn changed_entities() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let mut changes = Changes::new_for::<(&u32, &i32)>().with_entities();
+        changes.set_entity_changed(TypeId::of::<u32>(), a);
+        changes.set_entity_changed(TypeId::of::<u32>(), b);
+        changes.set_entity_changed(TypeId::of::<i32>(), b);
+
+        assert_eq!(changes.changed_entities::<u32>(), vec![a, b]);
+        assert_eq!(changes.changed_entities::<i32>(), vec![b]);
+        assert!(changes.is_entity_changed(TypeId::of::<i32>(), b));
+        assert!(!changes.is_entity_changed(TypeId::of::<i32>(), a));
+
+        changes.reset();
+        assert!(changes.changed_entities::<u32>().is_empty());
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+    }
+
+    #[test]
+    fn changed_entities_disabled() {
+        let mut world = World::new();
+        let a = world.spawn(());
+
+        let changes = Changes::new_for::<&u32>();
+        changes.set_entity_changed(TypeId::of::<u32>(), a);
+
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert!(changes.changed_entities::<u32>().is_empty());
+    }
+
+    #[test]
+    fn changed_since() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let changes = Changes::new_for::<(&u32, &i32)>().with_entities();
+        let first_consumer = 0;
+        let second_consumer = changes.advance_tick();
+
+        changes.set_entity_changed(TypeId::of::<u32>(), a);
+        let third_consumer = changes.advance_tick();
+        changes.set_entity_changed(TypeId::of::<i32>(), b);
+
+        assert!(changes.changed_since(TypeId::of::<u32>(), first_consumer));
+        assert!(changes.changed_since(TypeId::of::<u32>(), second_consumer));
+        assert!(!changes.changed_since(TypeId::of::<u32>(), third_consumer));
+        assert!(changes.changed_since(TypeId::of::<i32>(), third_consumer));
+        assert!(!changes.changed_since(TypeId::of::<f32>(), first_consumer));
+
+        assert!(changes.is_entity_changed_since(TypeId::of::<u32>(), a, second_consumer));
+        assert!(!changes.is_entity_changed_since(TypeId::of::<u32>(), a, third_consumer));
+
+        let mut entities = vec![];
+        changes.for_each_changed_entity_since(TypeId::of::<i32>(), third_consumer, |e| {
+            entities.push(e)
+        });
+        assert_eq!(entities, vec![b]);
+        assert_eq!(
+            changes.changed_tick(TypeId::of::<i32>()),
+            Some(changes.tick())
+        );
+    }
+
+    #[test]
+    fn many_types() {
+        macro_rules! type_ids {
+            ($($n: literal)*) => { vec![$(TypeId::of::<[u8; $n]>()),*] };
+        }
+        #[rustfmt::skip]
+        let type_ids = type_ids!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63 64 65 66 67 68 69);
+
+        let mut changes = Changes::new();
+        type_ids.iter().for_each(|t| changes.reserve(*t));
+
+        changes.set_changed(type_ids[3]);
+        changes.set_changed(type_ids[64]);
+
+        let mut changed = vec![];
+        changes.for_each_changed(|t| changed.push(t));
+        assert_eq!(changed, vec![type_ids[3], type_ids[64]]);
+        assert_eq!(changes.iter().count(), type_ids.len());
+    }
+
+    #[test]
+    fn slots() {
+        let changes = Changes::new_for::<&u32>();
+        let slots = changes.slots_for::<(&u32, &mut i32)>();
+        assert!(slots[0].is_reserved());
+        assert!(!slots[1].is_reserved());
+
+        slots[0].set_changed();
+        assert!(slots[0].is_changed());
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert!(!slots[1].is_changed());
+    }
+
+    #[test]
+    #[should_panic]
+    fn unreserved_slot() {
+        let changes = Changes::new();
+        changes.slot(TypeId::of::<u32>()).set_changed();
+    }
+
+    #[test]
+    fn auto_reserve_shared() {
+        let changes = Changes::new().with_auto_reserve();
+        std::thread::scope(|scope| {
+            scope.spawn(|| changes.set_changed(TypeId::of::<u32>()));
+            scope.spawn(|| changes.set_changed(TypeId::of::<i32>()));
+        });
+
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert!(changes.is_changed(TypeId::of::<i32>()));
+        assert!(changes.check_reserved::<&mut f32>().is_ok());
+    }
+
+    #[test]
+    fn take_changed() {
+        let changes = Changes::new_for::<(&u32, &i32, &f32)>();
+        changes.set_changed(TypeId::of::<u32>());
+        changes.set_changed(TypeId::of::<f32>());
+
+        let taken = changes.take_changed();
+        assert_eq!(taken.len(), 2);
+        assert!(taken.contains(&TypeId::of::<u32>()));
+        assert!(taken.contains(&TypeId::of::<f32>()));
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+
+        changes.set_changed(TypeId::of::<i32>());
+        let taken = changes.take_changed();
+        assert_eq!(
+            taken.into_iter().collect::<Vec<_>>(),
+            vec![TypeId::of::<i32>()]
+        );
+        assert!(changes.take_changed().is_empty());
+    }
+
+    #[test]
+    fn take_changed_records() {
+        let mut world = World::new();
+        let a = world.spawn(());
+
+        let changes = Changes::new_for::<&Vec<u32>>()
+            .with_entities()
+            .with_fields();
+        let slot = changes.slot(TypeId::of::<Vec<u32>>());
+        slot.set_field_changed(a, "len");
+        slot.set_key_changed(a, 1usize);
+        slot.set_added(a);
+
+        changes.take_changed();
+        assert!(changes.changed_entities::<Vec<u32>>().is_empty());
+        assert!(changes.changed_fields::<Vec<u32>>().is_empty());
+        assert!(changes.changed_keys::<Vec<u32>, usize>().is_empty());
+        assert!(changes.added_entities::<Vec<u32>>().is_empty());
+        assert!(changes.changed_since(TypeId::of::<Vec<u32>>(), 0));
+        assert!(!changes.is_entity_changed_since(TypeId::of::<Vec<u32>>(), a, 0));
+    }
+
+    #[test]
+    fn type_names() {
+        let mut changes = Changes::new_for::<&u32>();
+        changes.reserve(TypeId::of::<i32>());
+        changes.reserve_type::<f32>();
+        changes.set_changed(TypeId::of::<u32>());
+
+        assert_eq!(changes.type_name(TypeId::of::<u32>()), Some("u32"));
+        assert_eq!(changes.type_name(TypeId::of::<i32>()), None);
+        assert_eq!(changes.type_name(TypeId::of::<u64>()), None);
+
+        let _ = changes.slots_for::<&mut i32>();
+        assert_eq!(changes.type_name(TypeId::of::<i32>()), Some("i32"));
+
+        let named: Vec<_> = changes
+            .iter_named()
+            .map(|(_, name, changed)| (name.unwrap(), changed))
+            .collect();
+        assert_eq!(named, vec![("u32", true), ("i32", false), ("f32", false)]);
+        assert_eq!(
+            format!("{:?}", changes),
+            r#"{u32: true, i32: false, f32: false}"#
+        );
+    }
+
+    #[test]
+    fn observers() {
+        use super::ObserveMode;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let immediate = Arc::new(AtomicUsize::new(0));
+        let deferred = Arc::new(AtomicUsize::new(0));
+
+        let mut changes = Changes::new_for::<(&u32, &i32)>();
+        let counter = immediate.clone();
+        changes.observe_type::<u32>(ObserveMode::Immediate, move |t| {
+            assert_eq!(t, TypeId::of::<u32>());
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let counter = deferred.clone();
+        changes.observe(TypeId::of::<i32>(), ObserveMode::Deferred, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        changes.set_changed(TypeId::of::<u32>());
+        changes.set_changed(TypeId::of::<u32>());
+        changes.set_changed(TypeId::of::<i32>());
+        assert_eq!(immediate.load(Ordering::Relaxed), 1);
+        assert_eq!(deferred.load(Ordering::Relaxed), 0);
+
+        changes.dispatch();
+        changes.dispatch();
+        assert_eq!(deferred.load(Ordering::Relaxed), 1);
+
+        changes.take_changed();
+        changes.set_changed(TypeId::of::<u32>());
+        changes.set_changed(TypeId::of::<i32>());
+        changes.dispatch();
+        assert_eq!(immediate.load(Ordering::Relaxed), 2);
+        assert_eq!(deferred.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn events() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let changes = Changes::new_for::<(&u32, &i32)>().with_events();
+        changes.set_entity_changed(TypeId::of::<u32>(), a);
+        changes.set_entity_changed(TypeId::of::<i32>(), b);
+        changes.set_changed(TypeId::of::<i32>());
+
+        let events: Vec<_> = changes.drain_events().collect();
+        assert_eq!(
+            events,
+            vec![(a, TypeId::of::<u32>()), (b, TypeId::of::<i32>())]
+        );
+        assert_eq!(changes.drain_events().count(), 0);
+        assert_eq!(Changes::new().drain_events().count(), 0);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let first = Changes::new_for::<(&u32, &i32, &f32)>();
+        first.set_changed(TypeId::of::<u32>());
+        first.set_changed(TypeId::of::<i32>());
+        let second = Changes::new_for::<(&i32, &f64)>();
+        second.set_changed(TypeId::of::<i32>());
+        second.set_changed(TypeId::of::<f64>());
+
+        let union = first.union(&second);
+        assert_eq!(union.len(), 3);
+        assert!(union.contains(&TypeId::of::<f64>()));
+        assert_eq!(
+            first.intersection(&second).into_iter().collect::<Vec<_>>(),
+            vec![TypeId::of::<i32>()]
+        );
+        assert_eq!(
+            first.difference(&second).into_iter().collect::<Vec<_>>(),
+            vec![TypeId::of::<u32>()]
+        );
+
+        assert!(first.is_any_changed(&[TypeId::of::<f32>(), TypeId::of::<u32>()]));
+        assert!(!first.is_any_changed(&[TypeId::of::<f32>(), TypeId::of::<f64>()]));
+        assert_eq!(
+            first.changed_among::<(f32, i32, f64)>(),
+            vec![TypeId::of::<i32>()]
+        );
+    }
+
+    #[test]
+    fn merge_into() {
+        let mut world = World::new();
+        let a = world.spawn(());
+
+        let first = Changes::new_for::<(&u32, &i32)>().with_entities();
+        first.set_entity_changed(TypeId::of::<u32>(), a);
+        let mut second = Changes::new_for::<&f32>().with_entities();
+
+        first.merge_into(&mut second);
+        assert!(second.is_changed(TypeId::of::<u32>()));
+        assert!(!second.is_changed(TypeId::of::<i32>()));
+        assert!(second.is_entity_changed(TypeId::of::<u32>(), a));
+        assert_eq!(second.type_name(TypeId::of::<i32>()), Some("i32"));
+    }
+
+    #[test]
+    fn merge_into_strict() {
+        let first = Changes::new_for::<&mut u32>();
+        first.slot(TypeId::of::<u32>()).set_changed();
+        let mut second = Changes::new().with_strict();
+
+        first.merge_into(&mut second);
+        assert!(second.is_changed(TypeId::of::<u32>()));
+        assert!(second.is_mutable(TypeId::of::<u32>()));
+        assert!(second.rejected().is_empty());
+    }
+
+    #[test]
+    fn merge_into_unchanged() {
+        let mut world = World::new();
+        let a = world.spawn(());
+
+        let first = Changes::new_for::<&u32>().with_entities();
+        first.set_entity_changed(TypeId::of::<u32>(), a);
+        first.take_changed();
+        first.slot(TypeId::of::<u32>()).set_added(a);
+        let mut second = Changes::new().with_events();
+
+        first.merge_into(&mut second);
+        assert!(!second.is_changed(TypeId::of::<u32>()));
+        assert!(second.is_entity_added(TypeId::of::<u32>(), a));
+        assert_eq!(second.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn changed_fields() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let changes = Changes::new_for::<&u32>().with_entities().with_fields();
+        let slot = changes.slot(TypeId::of::<u32>());
+        slot.set_field_changed(a, "y");
+        slot.set_field_changed(b, "x");
+        slot.set_field_changed(b, "y");
+
+        assert!(changes.is_entity_changed(TypeId::of::<u32>(), a));
+        assert_eq!(changes.changed_fields::<u32>(), vec!["x", "y"]);
+        assert_eq!(changes.changed_entity_fields::<u32>(a), vec!["y"]);
+        assert_eq!(changes.changed_entity_fields::<u32>(b), vec!["x", "y"]);
+
+        let changes = Changes::new_for::<&u32>().with_entities();
+        changes.slot(TypeId::of::<u32>()).set_field_changed(a, "y");
+        assert!(changes.is_entity_changed(TypeId::of::<u32>(), a));
+        assert!(changes.changed_fields::<u32>().is_empty());
+    }
+
+    #[test]
+    fn changed_keys() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let mut changes = Changes::new_for::<&Vec<u32>>();
+        let slot = changes.slot(TypeId::of::<Vec<u32>>());
+        slot.set_key_changed(a, 3usize);
+        slot.set_key_changed(b, 1usize);
+        slot.set_key_changed(b, 1usize);
+
+        let mut keys = changes.changed_keys::<Vec<u32>, usize>();
+        keys.sort();
+        assert_eq!(keys, vec![(a, 3), (b, 1)]);
+        assert_eq!(changes.changed_entity_keys::<Vec<u32>, usize>(b), vec![1]);
+        assert!(changes.changed_keys::<Vec<u32>, u8>().is_empty());
+
+        changes.reset();
+        assert!(changes.changed_keys::<Vec<u32>, usize>().is_empty());
+    }
+
+    #[test]
+    fn strict() {
+        let mut world = World::new();
+        let a = world.spawn((1u32, 2i32));
+
+        let mut changes = Changes::new_for::<(&mut u32, &i32, &u8)>()
+            .with_strict()
+            .with_entities();
+        changes.reserve_interior_mutable::<u8>();
+        assert!(changes.is_mutable(TypeId::of::<u32>()));
+        assert!(!changes.is_mutable(TypeId::of::<i32>()));
+        assert!(changes.is_mutable(TypeId::of::<u8>()));
+
+        changes.slot(TypeId::of::<u32>()).set_entity_changed(a);
+        changes.slot(TypeId::of::<i32>()).set_entity_changed(a);
+        changes.slot(TypeId::of::<u8>()).set_changed();
+
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert!(!changes.is_changed(TypeId::of::<i32>()));
+        assert!(!changes.is_entity_changed(TypeId::of::<i32>(), a));
+        assert!(changes.is_changed(TypeId::of::<u8>()));
+        assert_eq!(
+            changes.rejected().into_iter().collect::<Vec<_>>(),
+            vec![TypeId::of::<i32>()]
+        );
+
+        changes.reset();
+        assert!(changes.rejected().is_empty());
+    }
+}
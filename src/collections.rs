@@ -0,0 +1,350 @@
+use crate::{ChangeSlot, TrackedMut};
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+use hecs::Entity;
+use std::collections::{BTreeMap, HashMap};
+
+/// Tracked mutable reference to an element of a `Vec`, `HashMap` or `BTreeMap` component,
+/// returned by the `element_mut` and `elements_mut` methods of [`TrackedMut`] of these collections.
+/// They are named apart from `get_mut` and `iter_mut` of the collections, which still flag the whole value.
+///
+/// The first mutable access flags the collection type and records the key of the element,
+/// see [`crate::Changes::changed_keys`].
+pub struct ElementMut<'a, T, K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    value: &'a mut T,
+    key: K,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    /// Whether the collection is flagged, shared with the [`TrackedMut`] and the other elements.
+    collection_mutated: &'a AtomicBool,
+    mutated: bool,
+}
+
+impl<'a, T, K> ElementMut<'a, T, K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn new(
+        value: &'a mut T,
+        key: K,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        collection_mutated: &'a AtomicBool,
+    ) -> Self {
+        Self {
+            value,
+            key,
+            entity,
+            slot,
+            collection_mutated,
+            mutated: false,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Index or key of the element in the collection.
+    #[inline]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    #[inline]
+    pub fn set_mutated(&self) {
+        record_keys(
+            self.slot,
+            self.entity,
+            self.collection_mutated,
+            [self.key.clone()],
+        )
+    }
+}
+
+/// Flag the collection once per [`TrackedMut`], so the change events are not repeated, and record `keys`.
+fn record_keys<K>(
+    slot: &ChangeSlot<'_>,
+    entity: Entity,
+    mutated: &AtomicBool,
+    keys: impl IntoIterator<Item = K>,
+) where
+    K: Hash + Eq + Send + Sync + 'static,
+{
+    if !mutated.swap(true, Ordering::Relaxed) {
+        slot.set_entity_changed(entity);
+    }
+    slot.record_keys(entity, keys);
+}
+
+impl<'a, T, K> core::fmt::Debug for ElementMut<'a, T, K>
+where
+    T: core::fmt::Debug,
+    K: core::fmt::Debug + Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("ElementMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &self.value)
+            .field("key", &self.key)
+            .field("entity", &self.entity)
+            .field("mutated", &self.mutated)
+            .finish()
+    }
+}
+
+impl<'a, T, K> Deref for ElementMut<'a, T, K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T, K> DerefMut for ElementMut<'a, T, K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !self.mutated {
+            self.set_mutated();
+            self.mutated = true;
+        }
+        &mut *(self.value)
+    }
+}
+
+/// Element-wise tracking of `Vec` components, the changed indices are recorded as `usize` keys.
+impl<'a, T> TrackedMut<'a, Vec<T>>
+where
+    T: 'static,
+{
+    pub fn push(&mut self, value: T) {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        vec.push(value);
+        record_keys(slot, entity, mutated, [vec.len() - 1]);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        let value = vec.pop();
+        if value.is_some() {
+            record_keys(slot, entity, mutated, [vec.len()]);
+        }
+        value
+    }
+
+    /// Insert `value` at `index`, all following elements are recorded as changed too.
+    pub fn insert(&mut self, index: usize, value: T) {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        vec.insert(index, value);
+        record_keys(slot, entity, mutated, index..vec.len());
+    }
+
+    /// Remove the element at `index`, all following elements are recorded as changed too.
+    pub fn remove(&mut self, index: usize) -> T {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        let value = vec.remove(index);
+        record_keys(slot, entity, mutated, index..=vec.len());
+        value
+    }
+
+    pub fn element_mut(&mut self, index: usize) -> Option<ElementMut<'_, T, usize>> {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        vec.get_mut(index)
+            .map(|value| ElementMut::new(value, index, entity, slot, mutated))
+    }
+
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = ElementMut<'_, T, usize>> {
+        let (vec, entity, slot, mutated) = self.parts_mut();
+        vec.iter_mut()
+            .enumerate()
+            .map(move |(index, value)| ElementMut::new(value, index, entity, slot, mutated))
+    }
+}
+
+macro_rules! tracked_map_impl {
+    ($($bound:tt)+) => {
+        pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+            let (map, entity, slot, mutated) = self.parts_mut();
+            let value = map.insert(key.clone(), value);
+            record_keys(slot, entity, mutated, [key]);
+            value
+        }
+
+        pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where
+            K: Borrow<Q>,
+            Q: $($bound)+ + ?Sized,
+        {
+            let (map, entity, slot, mutated) = self.parts_mut();
+            let (key, value) = map.remove_entry(key)?;
+            record_keys(slot, entity, mutated, [key]);
+            Some(value)
+        }
+
+        pub fn element_mut<Q>(&mut self, key: &Q) -> Option<ElementMut<'_, V, K>>
+        where
+            K: Borrow<Q>,
+            Q: $($bound)+ + ?Sized,
+        {
+            let (map, entity, slot, mutated) = self.parts_mut();
+            let owned = map.get_key_value(key)?.0.clone();
+            map.get_mut(key)
+                .map(|value| ElementMut::new(value, owned, entity, slot, mutated))
+        }
+
+        pub fn elements_mut(&mut self) -> impl Iterator<Item = ElementMut<'_, V, K>> {
+            let (map, entity, slot, mutated) = self.parts_mut();
+            map.iter_mut()
+                .map(move |(key, value)| ElementMut::new(value, key.clone(), entity, slot, mutated))
+        }
+    };
+}
+
+/// Element-wise tracking of `HashMap` components, the changed keys are recorded.
+impl<'a, K, V, S> TrackedMut<'a, HashMap<K, V, S>>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    V: 'static,
+    S: BuildHasher + 'static,
+{
+    tracked_map_impl!(Hash + Eq);
+}
+
+/// Element-wise tracking of `BTreeMap` components, the changed keys are recorded.
+impl<'a, K, V> TrackedMut<'a, BTreeMap<K, V>>
+where
+    K: Ord + Hash + Clone + Send + Sync + 'static,
+    V: 'static,
+{
+    tracked_map_impl!(Ord);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Changes, TrackableQuery};
+    use core::any::TypeId;
+    use hecs::World;
+    use std::collections::HashMap;
+
+    #[test]
+    fn tracked_vec() {
+        let mut world = World::new();
+        let a = world.spawn((vec![1u32, 2, 3],));
+
+        let changes = Changes::new_for::<&Vec<u32>>();
+        for (_, mut items) in <&mut Vec<u32>>::track(&changes).query(&world).iter() {
+            for item in items.elements_mut() {
+                assert!(*item < 4);
+            }
+            assert!(!changes.is_changed(TypeId::of::<Vec<u32>>()));
+
+            *items.element_mut(1).unwrap() = 5;
+            items.push(4);
+        }
+
+        let mut keys = changes.changed_entity_keys::<Vec<u32>, usize>(a);
+        keys.sort();
+        assert_eq!(keys, vec![1, 3]);
+        assert!(changes.is_changed(TypeId::of::<Vec<u32>>()));
+        assert_eq!(*world.get::<Vec<u32>>(a).unwrap(), vec![1, 5, 3, 4]);
+    }
+
+    #[test]
+    fn tracked_vec_remove() {
+        let mut world = World::new();
+        let a = world.spawn((vec![1u32, 2, 3, 4],));
+
+        let changes = Changes::new_for::<&Vec<u32>>();
+        for (_, mut items) in <&mut Vec<u32>>::track(&changes).query(&world).iter() {
+            assert_eq!(items.remove(2), 3);
+        }
+
+        let mut keys = changes.changed_entity_keys::<Vec<u32>, usize>(a);
+        keys.sort();
+        assert_eq!(keys, vec![2, 3]);
+    }
+
+    #[test]
+    fn tracked_map() {
+        let mut world = World::new();
+        let map: HashMap<&'static str, u32> = [("sword", 1), ("shield", 1)].into_iter().collect();
+        let a = world.spawn((map,));
+
+        type Inventory = HashMap<&'static str, u32>;
+        let changes = Changes::new_for::<&Inventory>();
+        for (_, mut items) in <&mut Inventory>::track(&changes).query(&world).iter() {
+            for mut item in items.elements_mut() {
+                if *item.key() == "sword" {
+                    *item += 1;
+                }
+            }
+            items.insert("potion", 3);
+            assert_eq!(items.remove(&"arrow"), None);
+        }
+
+        let mut keys = changes.changed_entity_keys::<Inventory, &'static str>(a);
+        keys.sort();
+        assert_eq!(keys, vec!["potion", "sword"]);
+        assert_eq!(world.get::<Inventory>(a).unwrap()["sword"], 2);
+    }
+
+    #[test]
+    fn tracked_vec_events() {
+        let mut world = World::new();
+        let a = world.spawn((vec![1u32],));
+
+        let changes = Changes::new_for::<&Vec<u32>>().with_events();
+        for (_, mut items) in <&mut Vec<u32>>::track(&changes).query(&world).iter() {
+            items.push(2);
+            items.push(3);
+            *items.element_mut(0).unwrap() = 5;
+            for mut item in items.elements_mut() {
+                *item += 1;
+            }
+        }
+
+        let events: Vec<_> = changes.drain_events().collect();
+        assert_eq!(events, vec![(a, TypeId::of::<Vec<u32>>())]);
+        let mut keys = changes.changed_entity_keys::<Vec<u32>, usize>(a);
+        keys.sort();
+        assert_eq!(keys, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn tracked_map_borrowed_keys() {
+        let mut world = World::new();
+        let map: HashMap<String, u32> = [("sword".to_string(), 1)].into_iter().collect();
+        let a = world.spawn((map,));
+
+        type Inventory = HashMap<String, u32>;
+        let changes = Changes::new_for::<&Inventory>().with_fields();
+        for (_, mut items) in <&mut Inventory>::track(&changes).query(&world).iter() {
+            *items.element_mut("sword").unwrap() += 1;
+            assert_eq!(items.remove("arrow"), None);
+        }
+        assert_eq!(
+            changes.changed_entity_keys::<Inventory, String>(a),
+            vec!["sword".to_string()]
+        );
+        assert!(changes.changed_fields::<Inventory>().is_empty());
+
+        for (_, mut items) in <&mut Inventory>::track(&changes).query(&world).iter() {
+            // The methods of the map flag the whole value.
+            *items.get_mut("sword").unwrap() += 1;
+        }
+        assert_eq!(
+            changes.changed_fields::<Inventory>(),
+            vec![Changes::WHOLE_VALUE]
+        );
+        assert_eq!(world.get::<Inventory>(a).unwrap()["sword"], 3);
+    }
+}
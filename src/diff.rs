@@ -0,0 +1,261 @@
+use crate::ChangeSlot;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+use hecs::Entity;
+use std::collections::hash_map::DefaultHasher;
+
+/// Tracked mutable reference flagging the change only if the value really changed,
+/// see [`crate::TrackedMut::diff`].
+///
+/// The value is cloned on the first mutable access and compared with the original one on drop.
+pub struct DiffMut<'a, T>
+where
+    T: 'static + PartialEq + Clone,
+{
+    value: &'a mut T,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    original: Option<T>,
+    /// Whether the entity is already flagged.
+    flagged: bool,
+}
+
+impl<'a, T> DiffMut<'a, T>
+where
+    T: 'static + PartialEq + Clone,
+{
+    pub(crate) fn new(
+        value: &'a mut T,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        flagged: bool,
+    ) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            original: None,
+            flagged,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Whether the value differs from the original one.
+    pub fn is_modified(&self) -> bool {
+        self.original
+            .as_ref()
+            .is_some_and(|original| original != self.value)
+    }
+}
+
+impl<'a, T> Drop for DiffMut<'a, T>
+where
+    T: 'static + PartialEq + Clone,
+{
+    fn drop(&mut self) {
+        if self.is_modified() {
+            self.slot
+                .set_value_changed_once(self.entity, &mut self.flagged)
+        }
+    }
+}
+
+impl<'a, T> core::fmt::Debug for DiffMut<'a, T>
+where
+    T: core::fmt::Debug + PartialEq + Clone,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("DiffMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &self.value)
+            .field("entity", &self.entity)
+            .field("modified", &self.is_modified())
+            .finish()
+    }
+}
+
+impl<'a, T> Deref for DiffMut<'a, T>
+where
+    T: 'static + PartialEq + Clone,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T> DerefMut for DiffMut<'a, T>
+where
+    T: 'static + PartialEq + Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if self.original.is_none() {
+            self.original = Some(self.value.clone());
+        }
+        self.value
+    }
+}
+
+/// Tracked mutable reference flagging the change only if the hash of the value changed,
+/// see [`crate::TrackedMut::hash_diff`].
+///
+/// Unlike [`DiffMut`] the value is not cloned, it is hashed on the first mutable access and on drop.
+pub struct HashDiffMut<'a, T>
+where
+    T: 'static + Hash,
+{
+    value: &'a mut T,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    original: Option<u64>,
+    /// Whether the entity is already flagged.
+    flagged: bool,
+}
+
+impl<'a, T> HashDiffMut<'a, T>
+where
+    T: 'static + Hash,
+{
+    pub(crate) fn new(
+        value: &'a mut T,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        flagged: bool,
+    ) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            original: None,
+            flagged,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Whether the hash of the value differs from the original one.
+    pub fn is_modified(&self) -> bool {
+        self.original
+            .is_some_and(|original| original != hash(&*self.value))
+    }
+}
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<'a, T> Drop for HashDiffMut<'a, T>
+where
+    T: 'static + Hash,
+{
+    fn drop(&mut self) {
+        if self.is_modified() {
+            self.slot
+                .set_value_changed_once(self.entity, &mut self.flagged)
+        }
+    }
+}
+
+impl<'a, T> core::fmt::Debug for HashDiffMut<'a, T>
+where
+    T: core::fmt::Debug + Hash,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("HashDiffMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &self.value)
+            .field("entity", &self.entity)
+            .field("modified", &self.is_modified())
+            .finish()
+    }
+}
+
+impl<'a, T> Deref for HashDiffMut<'a, T>
+where
+    T: 'static + Hash,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T> DerefMut for HashDiffMut<'a, T>
+where
+    T: 'static + Hash,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if self.original.is_none() {
+            self.original = Some(hash(&*self.value));
+        }
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Changes, TrackableRef};
+    use core::any::TypeId;
+    use hecs::Entity;
+
+    #[test]
+    fn diff_same_value() {
+        let mut value = 72u32;
+        let reference = &mut value;
+        let changes = Changes::new_for::<&u32>();
+        let slots = changes.slots_for::<&mut u32>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots).diff();
+
+        *tracked = (*tracked).clamp(0, 100);
+        assert!(!tracked.is_modified());
+        drop(tracked);
+
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+    }
+
+    #[test]
+    fn diff_changed_value() {
+        let mut value = 72u32;
+        let reference = &mut value;
+        let changes = Changes::new_for::<&u32>();
+        let slots = changes.slots_for::<&mut u32>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots).diff();
+
+        *tracked = 69;
+        assert!(tracked.is_modified());
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+        drop(tracked);
+
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert_eq!(value, 69);
+    }
+
+    #[test]
+    fn hash_diff() {
+        let mut value = vec![1u32, 2, 3];
+        let changes = Changes::new_for::<&Vec<u32>>();
+        let slots = changes.slots_for::<&mut Vec<u32>>();
+
+        let mut tracked = (&mut value)
+            .into_tracked(Entity::DANGLING, &slots)
+            .hash_diff();
+        tracked.sort();
+        drop(tracked);
+        assert!(!changes.is_changed(TypeId::of::<Vec<u32>>()));
+
+        let mut tracked = (&mut value)
+            .into_tracked(Entity::DANGLING, &slots)
+            .hash_diff();
+        tracked.push(4);
+        assert!(tracked.is_modified());
+        drop(tracked);
+        assert!(changes.is_changed(TypeId::of::<Vec<u32>>()));
+    }
+}
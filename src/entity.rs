@@ -0,0 +1,244 @@
+use crate::{ChangeSlot, Changes, TrackableRef, TrackedMut, TrackedQueryOne};
+use core::any::{type_name, TypeId};
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+use hecs::{Component, Entity, EntityRef, Query, QueryItem, Ref, RefMut};
+
+/// Tracked counterpart of [`hecs::RefMut`] flagging the type and entity on the first mutable access,
+/// see [`TrackedEntityRef::get_mut`] and [`crate::TrackedWorld::get_mut`].
+/// Mutating a type that is not reserved in [`Changes`] panics, same as [`TrackedMut`].
+pub struct TrackedRefMut<'a, T>
+where
+    T: Component,
+{
+    inner: RefMut<'a, T>,
+    entity: Entity,
+    slot: ChangeSlot<'a>,
+    /// Same as the state of [`TrackedMut`], which is passed to [`TrackedRefMut::tracked`].
+    mutated: AtomicBool,
+    value_mutated: bool,
+}
+
+impl<'a, T> TrackedRefMut<'a, T>
+where
+    T: Component,
+{
+    /// Wrap `inner` borrowed from `entity`, the changes are reported to `changes`.
+    pub fn new(inner: RefMut<'a, T>, entity: Entity, changes: &'a Changes) -> Self {
+        let slot = changes.slot(TypeId::of::<T>());
+        if slot.is_reserved() {
+            slot.set_type_name(type_name::<T>());
+            slot.set_mutable();
+        }
+        Self {
+            inner,
+            entity,
+            slot,
+            mutated: AtomicBool::new(false),
+            value_mutated: false,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Flag the type and entity, only the first call or mutable access is reported.
+    #[inline]
+    pub fn set_mutated(&self) {
+        if !self.mutated.swap(true, Ordering::Relaxed) {
+            self.slot.set_entity_changed(self.entity)
+        }
+    }
+
+    /// Mutably borrow a field of the value, recording only that field as changed, see [`TrackedMut::field_mut`].
+    pub fn field_mut<F>(
+        &mut self,
+        field: &'static str,
+        f: impl FnOnce(&mut T) -> &mut F,
+    ) -> &mut F {
+        self.slot
+            .set_field_changed_once(self.entity, field, self.mutated.get_mut());
+        f(&mut self.inner)
+    }
+
+    /// Access the value as [`TrackedMut`], i.e. for the field accessors or the collection helpers.
+    /// Mutations already reported through `self` are not reported again and vice versa.
+    pub fn tracked<R>(&mut self, f: impl FnOnce(&mut TrackedMut<'_, T>) -> R) -> R {
+        let mut tracked = TrackedMut::with_state(
+            &mut *self.inner,
+            self.entity,
+            &self.slot,
+            *self.mutated.get_mut(),
+            self.value_mutated,
+        );
+        let result = f(&mut tracked);
+        (*self.mutated.get_mut(), self.value_mutated) = tracked.into_state();
+        result
+    }
+}
+
+impl<'a, T> core::fmt::Debug for TrackedRefMut<'a, T>
+where
+    T: Component + core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("TrackedRefMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &*self.inner)
+            .field("entity", &self.entity)
+            .field("mutated", &self.slot.is_changed())
+            .finish()
+    }
+}
+
+impl<'a, T> Deref for TrackedRefMut<'a, T>
+where
+    T: Component,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, T> DerefMut for TrackedRefMut<'a, T>
+where
+    T: Component,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Only the first mutation is reported, so the change events are not repeated.
+        if !self.value_mutated {
+            self.value_mutated = true;
+            self.slot
+                .set_value_changed_once(self.entity, self.mutated.get_mut());
+        }
+        &mut self.inner
+    }
+}
+
+/// Tracked counterpart of [`hecs::EntityRef`], i.e. `TrackedEntityRef::new(world.entity(entity)?, &changes)`.
+#[derive(Clone, Copy)]
+pub struct TrackedEntityRef<'a> {
+    inner: EntityRef<'a>,
+    changes: &'a Changes,
+}
+
+impl<'a> TrackedEntityRef<'a> {
+    pub fn new(inner: EntityRef<'a>, changes: &'a Changes) -> Self {
+        Self { inner, changes }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.inner.entity()
+    }
+
+    pub fn has<T: Component>(&self) -> bool {
+        self.inner.has::<T>()
+    }
+
+    /// Borrow the component of type `T`, if it exists, same as [`EntityRef::get`].
+    pub fn get<T: Component>(&self) -> Option<Ref<'a, T>> {
+        self.inner.get::<T>()
+    }
+
+    /// Uniquely borrow the component of type `T`, if it exists, same as [`EntityRef::get_mut`].
+    pub fn get_mut<T: Component>(&self) -> Option<TrackedRefMut<'a, T>> {
+        self.inner
+            .get_mut::<T>()
+            .map(|inner| TrackedRefMut::new(inner, self.entity(), self.changes))
+    }
+
+    /// Run a tracked query against this entity, same as [`EntityRef::query`].
+    pub fn query<Q: Query>(&self) -> TrackedQueryOne<'a, Q>
+    where
+        QueryItem<'a, Q>: TrackableRef<'a>,
+    {
+        TrackedQueryOne::with_slots(
+            self.inner.query::<Q>(),
+            self.entity(),
+            self.changes.slots_for::<QueryItem<'a, Q>>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackedEntityRef;
+    use crate::Changes;
+    use core::any::TypeId;
+    use hecs::World;
+
+    #[test]
+    fn tracked_entity_ref() {
+        let mut world = World::new();
+        let a = world.spawn((1u32, 2i32));
+
+        let changes = Changes::new_for::<(&u32, &i32)>().with_entities();
+        let entity = TrackedEntityRef::new(world.entity(a).unwrap(), &changes);
+
+        let mut value = entity.get_mut::<u32>().unwrap();
+        assert_eq!(*value, 1);
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+
+        *value += 1;
+        drop(value);
+        assert!(entity.get_mut::<u64>().is_none());
+        assert_eq!(*entity.get::<u32>().unwrap(), 2);
+
+        if let Some(mut value) = entity.query::<&mut i32>().get() {
+            *value = 3;
+        }
+
+        assert_eq!(changes.changed_entities::<u32>(), vec![a]);
+        assert_eq!(changes.changed_entities::<i32>(), vec![a]);
+        assert!(changes.is_mutable(TypeId::of::<u32>()));
+        assert_eq!(*world.get::<i32>(a).unwrap(), 3);
+    }
+
+    #[test]
+    fn tracked_ref_mut_fields() {
+        let mut world = World::new();
+        let a = world.spawn((1u32, vec![1u8]));
+
+        let changes = Changes::new_for::<(&u32, &Vec<u8>)>()
+            .with_entities()
+            .with_fields()
+            .with_events();
+        let entity = TrackedEntityRef::new(world.entity(a).unwrap(), &changes);
+
+        let mut value = entity.get_mut::<u32>().unwrap();
+        value.set_mutated();
+        *value.field_mut("value", |v| v) += 1;
+        *value += 1;
+        drop(value);
+
+        let mut values = entity.get_mut::<Vec<u8>>().unwrap();
+        values.tracked(|values| values.push(2));
+        values[0] = 3;
+        drop(values);
+
+        assert_eq!(
+            changes.changed_fields::<u32>(),
+            vec![Changes::WHOLE_VALUE, "value"]
+        );
+        assert_eq!(
+            changes.changed_fields::<Vec<u8>>(),
+            vec![Changes::WHOLE_VALUE]
+        );
+        assert_eq!(changes.drain_events().count(), 2);
+        assert_eq!(*world.get::<Vec<u8>>(a).unwrap(), vec![3, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not reserved")]
+    fn unreserved_ref_mut() {
+        let mut world = World::new();
+        let a = world.spawn((1u32,));
+
+        let changes = Changes::new();
+        let entity = TrackedEntityRef::new(world.entity(a).unwrap(), &changes);
+        *entity.get_mut::<u32>().unwrap() += 1;
+    }
+}
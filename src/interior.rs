@@ -0,0 +1,248 @@
+//! Tracked mutations of interior-mutable components borrowed through [`TrackedRef`].
+//!
+//! Every mutating method flags the type and entity like [`TrackedRef::set_mutated`],
+//! so in the strict mode these types have to be reserved with [`crate::Changes::reserve_interior_mutable`].
+//! `Cell` and `RefCell` are not `Sync` and can not be `hecs` components,
+//! their wrappers are useful for the values tracked outside of the world.
+
+use crate::TrackedRef;
+use core::cell::{Cell, RefCell, RefMut};
+use core::sync::atomic::{
+    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32,
+    AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+use std::sync::{LockResult, Mutex, MutexGuard, RwLock, RwLockWriteGuard};
+
+impl<'a, T> TrackedRef<'a, Cell<T>>
+where
+    T: 'static,
+{
+    pub fn set(&self, value: T) {
+        self.set_mutated();
+        Cell::set(self, value)
+    }
+
+    pub fn replace(&self, value: T) -> T {
+        self.set_mutated();
+        Cell::replace(self, value)
+    }
+
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.set_mutated();
+        Cell::take(self)
+    }
+}
+
+impl<'a, T> TrackedRef<'a, RefCell<T>>
+where
+    T: 'static,
+{
+    /// Mutably borrow the value, the type is flagged even if the value is not written.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.set_mutated();
+        RefCell::borrow_mut(self)
+    }
+
+    pub fn replace(&self, value: T) -> T {
+        self.set_mutated();
+        RefCell::replace(self, value)
+    }
+}
+
+impl<'a, T> TrackedRef<'a, Mutex<T>>
+where
+    T: 'static,
+{
+    /// Lock the value, the type is flagged even if the value is not written.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        self.set_mutated();
+        Mutex::lock(self)
+    }
+}
+
+impl<'a, T> TrackedRef<'a, RwLock<T>>
+where
+    T: 'static,
+{
+    /// Lock the value for writing, the type is flagged even if the value is not written.
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.set_mutated();
+        RwLock::write(self)
+    }
+}
+
+macro_rules! tracked_atomic_impl {
+    ($atomic: ident, $value: ty) => {
+        impl<'a> TrackedRef<'a, $atomic> {
+            pub fn store(&self, value: $value, order: Ordering) {
+                self.set_mutated();
+                $atomic::store(self, value, order)
+            }
+
+            pub fn swap(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::swap(self, value, order)
+            }
+
+            /// The type is flagged only if the value is exchanged.
+            pub fn compare_exchange(
+                &self,
+                current: $value,
+                new: $value,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$value, $value> {
+                let result = $atomic::compare_exchange(self, current, new, success, failure);
+                if result.is_ok() {
+                    self.set_mutated();
+                }
+                result
+            }
+
+            pub fn fetch_and(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_and(self, value, order)
+            }
+
+            pub fn fetch_or(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_or(self, value, order)
+            }
+
+            pub fn fetch_xor(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_xor(self, value, order)
+            }
+        }
+    };
+    ($atomic: ident, $value: ty, integer) => {
+        tracked_atomic_impl!($atomic, $value);
+
+        impl<'a> TrackedRef<'a, $atomic> {
+            pub fn fetch_add(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_add(self, value, order)
+            }
+
+            pub fn fetch_sub(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_sub(self, value, order)
+            }
+
+            pub fn fetch_max(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_max(self, value, order)
+            }
+
+            pub fn fetch_min(&self, value: $value, order: Ordering) -> $value {
+                self.set_mutated();
+                $atomic::fetch_min(self, value, order)
+            }
+        }
+    };
+}
+
+tracked_atomic_impl!(AtomicBool, bool);
+tracked_atomic_impl!(AtomicU8, u8, integer);
+tracked_atomic_impl!(AtomicU16, u16, integer);
+tracked_atomic_impl!(AtomicU32, u32, integer);
+tracked_atomic_impl!(AtomicU64, u64, integer);
+tracked_atomic_impl!(AtomicUsize, usize, integer);
+tracked_atomic_impl!(AtomicI8, i8, integer);
+tracked_atomic_impl!(AtomicI16, i16, integer);
+tracked_atomic_impl!(AtomicI32, i32, integer);
+tracked_atomic_impl!(AtomicI64, i64, integer);
+tracked_atomic_impl!(AtomicIsize, isize, integer);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Changes, TrackableQuery, TrackableRef};
+    use core::any::TypeId;
+    use core::cell::Cell;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use hecs::{Entity, World};
+    use std::sync::Mutex;
+
+    #[test]
+    fn tracked_atomic() {
+        let mut world = World::new();
+        let a = world.spawn((AtomicU32::new(1),));
+        let b = world.spawn((AtomicU32::new(2),));
+
+        let mut changes = Changes::new().with_strict().with_entities();
+        changes.reserve_interior_mutable::<AtomicU32>();
+        for (entity, value) in <&AtomicU32>::track(&changes).query(&world).iter() {
+            if entity == b {
+                value.fetch_add(1, Ordering::Relaxed);
+            }
+            let _ = value.compare_exchange(5, 6, Ordering::Relaxed, Ordering::Relaxed);
+        }
+
+        assert_eq!(changes.changed_entities::<AtomicU32>(), vec![b]);
+        assert!(changes.rejected().is_empty());
+        assert_eq!(
+            world.get::<AtomicU32>(a).unwrap().load(Ordering::Relaxed),
+            1
+        );
+        assert_eq!(
+            world.get::<AtomicU32>(b).unwrap().load(Ordering::Relaxed),
+            3
+        );
+    }
+
+    #[test]
+    fn tracked_atomic_events() {
+        let mut world = World::new();
+        let a = world.spawn((AtomicU32::new(1),));
+
+        let changes = Changes::new_for::<&AtomicU32>().with_events();
+        for (_, value) in <&AtomicU32>::track(&changes).query(&world).iter() {
+            value.fetch_add(1, Ordering::Relaxed);
+            value.fetch_add(1, Ordering::Relaxed);
+            value.store(5, Ordering::Relaxed);
+        }
+
+        let events: Vec<_> = changes.drain_events().collect();
+        assert_eq!(events, vec![(a, TypeId::of::<AtomicU32>())]);
+    }
+
+    #[test]
+    fn try_track_atomic() {
+        let changes = Changes::new();
+        let error = <&AtomicU32>::try_track(&changes).err().unwrap();
+        assert_eq!(error.type_names(), &["core::sync::atomic::AtomicU32"]);
+        assert!(<&AtomicU32>::try_track(&Changes::new_for::<&AtomicU32>()).is_ok());
+    }
+
+    #[test]
+    fn tracked_mutex() {
+        let mut world = World::new();
+        world.spawn((Mutex::new(1u32),));
+
+        let changes = Changes::new_for::<&Mutex<u32>>();
+        for (_, value) in <&Mutex<u32>>::track(&changes).query(&world).iter() {
+            *value.lock().unwrap() += 1;
+        }
+
+        assert!(changes.is_changed(TypeId::of::<Mutex<u32>>()));
+    }
+
+    #[test]
+    fn tracked_cell() {
+        let value = Cell::new(1u32);
+        let reference = &value;
+        let changes = Changes::new_for::<&Cell<u32>>();
+        let slots = changes.slots_for::<&Cell<u32>>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
+
+        assert_eq!(tracked.get(), 1);
+        assert!(!changes.is_changed(TypeId::of::<Cell<u32>>()));
+
+        tracked.set(2);
+        assert!(changes.is_changed(TypeId::of::<Cell<u32>>()));
+        assert_eq!(value.get(), 2);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{ChangeSlot, Changes};
+use core::any::{type_name, TypeId};
+use core::ops::{Deref, DerefMut};
+use hecs::{Component, Entity, World};
+use std::sync::Mutex;
+
+trait Entry: Send {
+    /// Exchange the stored value with the one in the world and flag it in `changes`.
+    fn swap(&mut self, world: &mut World, changes: &Changes);
+}
+
+struct ComponentEntry<T> {
+    entity: Entity,
+    value: T,
+}
+
+impl<T> Entry for ComponentEntry<T>
+where
+    T: Component,
+{
+    fn swap(&mut self, world: &mut World, changes: &Changes) {
+        if let Ok(mut value) = world.get_mut::<T>(self.entity) {
+            core::mem::swap(&mut *value, &mut self.value);
+            let slot = changes.slot(TypeId::of::<T>());
+            if slot.is_reserved() {
+                slot.set_type_name(type_name::<T>());
+                slot.set_mutable();
+                slot.set_value_changed(self.entity);
+            }
+        }
+    }
+}
+
+type Transaction = Vec<Box<dyn Entry>>;
+
+/// Undo/redo journal of the component values mutated through [`JournalMut`],
+/// see [`crate::TrackedMut::journal`].
+///
+/// Mutations are grouped into transactions by [`Journal::commit`].
+/// Components of despawned entities are skipped when undoing or redoing,
+/// restored components of the types reserved in [`Changes`] are flagged as changed.
+#[derive(Default)]
+pub struct Journal {
+    current: Mutex<Transaction>,
+    undo: Vec<Transaction>,
+    redo: Vec<Transaction>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the value of the component of `entity` before its mutation.
+    pub fn record<T: Component>(&self, entity: Entity, value: T) {
+        self.current
+            .lock()
+            .unwrap()
+            .push(Box::new(ComponentEntry { entity, value }))
+    }
+
+    /// Close the current transaction, discarding the redo history if it is not empty.
+    pub fn commit(&mut self) {
+        let current = core::mem::take(self.current.get_mut().unwrap());
+        if !current.is_empty() {
+            self.undo.push(current);
+            self.redo.clear();
+        }
+    }
+
+    /// Commit the current transaction and revert the last one, returns `false` if there is nothing to undo.
+    pub fn undo(&mut self, world: &mut World, changes: &Changes) -> bool {
+        self.commit();
+        match self.undo.pop() {
+            Some(mut transaction) => {
+                transaction
+                    .iter_mut()
+                    .rev()
+                    .for_each(|entry| entry.swap(world, changes));
+                self.redo.push(transaction);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the last undone transaction, returns `false` if there is nothing to redo.
+    pub fn redo(&mut self, world: &mut World, changes: &Changes) -> bool {
+        match self.redo.pop() {
+            Some(mut transaction) => {
+                transaction
+                    .iter_mut()
+                    .for_each(|entry| entry.swap(world, changes));
+                self.undo.push(transaction);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.current.lock().unwrap().is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Tracked mutable reference recording the previous value into a [`Journal`].
+///
+/// The value is cloned into the journal on the first mutable access, which also flags the change.
+pub struct JournalMut<'a, T>
+where
+    T: Component + Clone,
+{
+    value: &'a mut T,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    journal: &'a Journal,
+    recorded: bool,
+    /// Whether the entity is already flagged.
+    flagged: bool,
+}
+
+impl<'a, T> JournalMut<'a, T>
+where
+    T: Component + Clone,
+{
+    pub(crate) fn new(
+        value: &'a mut T,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        journal: &'a Journal,
+        flagged: bool,
+    ) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            journal,
+            recorded: false,
+            flagged,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl<'a, T> core::fmt::Debug for JournalMut<'a, T>
+where
+    T: core::fmt::Debug + Component + Clone,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("JournalMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &self.value)
+            .field("entity", &self.entity)
+            .field("recorded", &self.recorded)
+            .finish()
+    }
+}
+
+impl<'a, T> Deref for JournalMut<'a, T>
+where
+    T: Component + Clone,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T> DerefMut for JournalMut<'a, T>
+where
+    T: Component + Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !self.recorded {
+            self.journal.record(self.entity, self.value.clone());
+            self.slot
+                .set_value_changed_once(self.entity, &mut self.flagged);
+            self.recorded = true;
+        }
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Journal;
+    use crate::{Changes, TrackableQuery};
+    use core::any::TypeId;
+    use hecs::World;
+
+    fn add(world: &World, changes: &Changes, journal: &Journal, delta: i32) {
+        <&mut i32>::track(changes)
+            .query(world)
+            .iter()
+            .for_each(|(_, value)| *value.journal(journal) += delta);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut world = World::new();
+        let a = world.spawn((1i32,));
+        let changes = Changes::new_for::<&i32>().with_entities();
+        let mut journal = Journal::new();
+
+        add(&world, &changes, &journal, 10);
+        add(&world, &changes, &journal, 100);
+        journal.commit();
+        add(&world, &changes, &journal, 1000);
+        assert_eq!(*world.get::<i32>(a).unwrap(), 1111);
+
+        changes.take_changed();
+        assert!(journal.undo(&mut world, &changes));
+        assert_eq!(changes.changed_entities::<i32>(), vec![a]);
+        assert_eq!(*world.get::<i32>(a).unwrap(), 111);
+        assert!(journal.undo(&mut world, &changes));
+        assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+        assert!(!journal.undo(&mut world, &changes));
+
+        changes.take_changed();
+        assert!(journal.redo(&mut world, &changes));
+        assert_eq!(*world.get::<i32>(a).unwrap(), 111);
+        assert!(changes.is_changed(TypeId::of::<i32>()));
+        assert!(journal.can_redo());
+
+        add(&world, &changes, &journal, 5);
+        journal.commit();
+        assert!(!journal.can_redo());
+        assert!(!journal.redo(&mut world, &changes));
+        assert!(journal.undo(&mut world, &changes));
+        assert_eq!(*world.get::<i32>(a).unwrap(), 111);
+    }
+}
@@ -1,38 +1,100 @@
 use core::any::TypeId;
+use hecs::Entity;
 
+/// Reference or a query item which can be wrapped into its tracked counterpart.
+///
+/// With the `derive` feature `#[derive(TrackableRef)]` implements it for a query struct,
+/// i.e. one with `#[derive(hecs::Query)]`, and generates the `Tracked<Struct>` struct of the tracked fields.
 pub trait TrackableRef<'a> {
     type Tracked: 'a;
+    /// Slots of the borrowed types, see [`TrackableRef::resolve_slots`].
+    type Slots: Copy + 'a;
 
     fn count_types() -> usize;
 
     /// Invoke `f` for every type that may be borrowed and whether the borrow is unique.
-    /// The second argument of `f` is `true` if the component is borrowed mutable.
-    fn for_each_type(f: impl FnMut(TypeId, bool));
+    /// The second argument of `f` is the type name
+    /// and the third one is `true` if the component is borrowed mutable.
+    fn for_each_type(f: impl FnMut(TypeId, &'static str, bool));
+
+    /// Pick the slots of the borrowed types out of `slots`, once per query.
+    /// `slots` are resolved with [`Changes::slots_for`] in the order of [`TrackableRef::for_each_type`].
+    fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots;
+
+    /// Wrap the references into their tracked counterparts.
+    /// Mutations are reported to `slots` on behalf of `entity`.
+    fn into_tracked_with(self, entity: Entity, slots: Self::Slots) -> Self::Tracked;
+
+    /// Same as [`TrackableRef::into_tracked_with`], but resolves `slots` first.
+    #[inline]
+    fn into_tracked(self, entity: Entity, slots: &'a [ChangeSlot<'a>]) -> Self::Tracked
+    where
+        Self: Sized,
+    {
+        self.into_tracked_with(entity, Self::resolve_slots(slots))
+    }
+}
+
+/// A tuple of component types, i.e. `(A, B, C)`, see [`Changes::changed_among`].
+pub trait ComponentSet {
+    fn for_each_type(f: impl FnMut(TypeId));
+}
 
-    fn into_tracked(self, changes: &'a Changes) -> Self::Tracked;
+/// Component which fields are tracked separately, see [`TrackedMut::field_mut`].
+///
+/// With the `derive` feature `#[derive(TrackedFields)]` implements it for a struct with named fields
+/// and generates the `<Struct>Fields` trait with the `<field>_mut` accessors for [`TrackedMut`].
+pub trait TrackedFields {
+    fn fields() -> &'static [&'static str];
 }
 
 /// Imagine macro parameters, but more like those Russian dolls.
 ///
 /// Calls m!(A, B, C), m!(A, B), m!(B), and m!() for i.e. (m, A, B, C)
 /// where m is any macro, for any number of parameters.
+/// Parameters may be token trees, i.e. `(A, a)`.
 macro_rules! smaller_tuples_too {
-    ($m: ident, $ty: ident) => {
+    ($m: ident, $ty: tt) => {
         $m!{}
         $m!{$ty}
     };
-    ($m: ident, $ty: ident, $($tt: ident),*) => {
+    ($m: ident, $ty: tt, $($tt: tt),*) => {
         smaller_tuples_too!{$m, $($tt),*}
         $m!{$ty, $($tt),*}
     };
 }
 
 mod changes;
+mod collections;
+mod diff;
+mod entity;
+mod interior;
+mod journal;
+mod log;
 mod option;
+mod or;
 mod query;
 mod references;
+mod satisfies;
 mod tuples;
+mod world;
 
-pub use changes::Changes;
-pub use query::{TrackableQuery, TrackedQueryBorrow, TrackedQueryBuilder, TrackedQueryIter};
+pub use changes::{
+    ChangeSlot, ChangeSlots, Changes, ChangesIter, DrainEvents, NamedChangesIter, NotReserved,
+    ObserveMode,
+};
+pub use collections::ElementMut;
+pub use diff::{DiffMut, HashDiffMut};
+pub use entity::{TrackedEntityRef, TrackedRefMut};
+/// The tracked `hecs`, used by the derive macros so dependents do not need to name it.
+pub use hecs;
+#[cfg(feature = "derive")]
+pub use hecs_query_tracker_derive::{TrackableRef, TrackedFields};
+pub use journal::{Journal, JournalMut};
+pub use log::{RecordMut, ValueChange, ValueLog};
+pub use query::{
+    TrackableQuery, TrackedQueryBorrow, TrackedQueryBuilder, TrackedQueryIter, TrackedQueryMut,
+    TrackedQueryOne,
+};
 pub use references::{TrackedMut, TrackedRef};
+pub use world::TrackedWorld;
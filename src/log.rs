@@ -0,0 +1,217 @@
+use crate::ChangeSlot;
+use core::ops::{Deref, DerefMut};
+use hecs::Entity;
+use std::sync::Mutex;
+
+/// Value of a component before and after it was mutated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueChange<T> {
+    pub entity: Entity,
+    pub old: T,
+    pub new: T,
+}
+
+/// Log of the component values recorded by [`RecordMut`], see [`crate::TrackedMut::record`].
+pub struct ValueLog<T> {
+    changes: Mutex<Vec<ValueChange<T>>>,
+}
+
+impl<T> Default for ValueLog<T> {
+    fn default() -> Self {
+        Self {
+            changes: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T> ValueLog<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, change: ValueChange<T>) {
+        self.changes.lock().unwrap().push(change)
+    }
+
+    /// Take all recorded changes in the order they were recorded.
+    pub fn take(&self) -> Vec<ValueChange<T>> {
+        core::mem::take(&mut *self.changes.lock().unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Tracked mutable reference recording the previous and the new value into a [`ValueLog`].
+///
+/// The value is cloned on the first mutable access, which also flags the change,
+/// and recorded into the log on drop.
+pub struct RecordMut<'a, T>
+where
+    T: 'static + Clone,
+{
+    value: &'a mut T,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    log: &'a ValueLog<T>,
+    old: Option<T>,
+    /// Whether the entity is already flagged.
+    flagged: bool,
+}
+
+impl<'a, T> RecordMut<'a, T>
+where
+    T: 'static + Clone,
+{
+    pub(crate) fn new(
+        value: &'a mut T,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        log: &'a ValueLog<T>,
+        flagged: bool,
+    ) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            log,
+            old: None,
+            flagged,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Value before the first mutable access, if any.
+    pub fn old(&self) -> Option<&T> {
+        self.old.as_ref()
+    }
+}
+
+impl<'a, T> Drop for RecordMut<'a, T>
+where
+    T: 'static + Clone,
+{
+    fn drop(&mut self) {
+        if let Some(old) = self.old.take() {
+            self.log.push(ValueChange {
+                entity: self.entity,
+                old,
+                new: self.value.clone(),
+            })
+        }
+    }
+}
+
+impl<'a, T> core::fmt::Debug for RecordMut<'a, T>
+where
+    T: core::fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(format!("RecordMut<{}>", std::any::type_name::<T>()).as_str())
+            .field("value", &self.value)
+            .field("entity", &self.entity)
+            .field("old", &self.old)
+            .finish()
+    }
+}
+
+impl<'a, T> Deref for RecordMut<'a, T>
+where
+    T: 'static + Clone,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T> DerefMut for RecordMut<'a, T>
+where
+    T: 'static + Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if self.old.is_none() {
+            self.old = Some(self.value.clone());
+            self.slot
+                .set_value_changed_once(self.entity, &mut self.flagged);
+        }
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValueChange, ValueLog};
+    use crate::{Changes, TrackableQuery};
+    use core::any::TypeId;
+    use hecs::World;
+
+    #[test]
+    fn record_values() {
+        let mut world = World::new();
+        let a = world.spawn((10u32,));
+        let b = world.spawn((20u32,));
+
+        let changes = Changes::new_for::<&u32>();
+        let log = ValueLog::new();
+
+        <&mut u32>::track(&changes)
+            .query(&world)
+            .iter()
+            .for_each(|(_, health)| {
+                let mut health = health.record(&log);
+                if *health > 15 {
+                    *health -= 5;
+                    *health -= 1;
+                }
+            });
+
+        assert!(changes.is_changed(TypeId::of::<u32>()));
+        assert_eq!(
+            log.take(),
+            vec![ValueChange {
+                entity: b,
+                old: 20,
+                new: 14
+            }]
+        );
+        assert!(log.is_empty());
+        assert_eq!(*world.get::<u32>(a).unwrap(), 10);
+    }
+    #[test]
+    fn record_after_mutation() {
+        let mut world = World::new();
+        let a = world.spawn((10u32,));
+
+        let changes = Changes::new_for::<&u32>().with_events();
+        let log = ValueLog::new();
+
+        for (_, mut health) in <&mut u32>::track(&changes).query(&world).iter() {
+            *health -= 1;
+            let mut health = health.record(&log);
+            *health -= 2;
+        }
+
+        assert_eq!(
+            changes.drain_events().collect::<Vec<_>>(),
+            vec![(a, TypeId::of::<u32>())]
+        );
+        assert_eq!(
+            log.take(),
+            vec![ValueChange {
+                entity: a,
+                old: 9,
+                new: 7
+            }]
+        );
+    }
+}
@@ -1,22 +1,30 @@
-use crate::{Changes, TrackableRef};
+use crate::{ChangeSlot, TrackableRef};
 use core::any::TypeId;
+use hecs::Entity;
 
 impl<'a, T> TrackableRef<'a> for Option<T>
 where
     T: TrackableRef<'a>,
 {
     type Tracked = Option<<T as TrackableRef<'a>>::Tracked>;
+    type Slots = <T as TrackableRef<'a>>::Slots;
 
     fn count_types() -> usize {
         <T as TrackableRef>::count_types()
     }
 
-    fn for_each_type(f: impl FnMut(TypeId, bool)) {
+    fn for_each_type(f: impl FnMut(TypeId, &'static str, bool)) {
         <T as TrackableRef>::for_each_type(f)
     }
 
-    fn into_tracked(self, changes: &'a Changes) -> Self::Tracked {
-        self.map(|value| value.into_tracked(changes))
+    #[inline]
+    fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots {
+        <T as TrackableRef>::resolve_slots(slots)
+    }
+
+    #[inline]
+    fn into_tracked_with(self, entity: Entity, slots: Self::Slots) -> Self::Tracked {
+        self.map(|value| value.into_tracked_with(entity, slots))
     }
 }
 
@@ -24,6 +32,7 @@ where
 mod tests {
     use crate::{Changes, TrackableRef};
     use core::any::TypeId;
+    use hecs::Entity;
 
     #[test]
     fn tracked_option_metadata() {
@@ -32,7 +41,7 @@ mod tests {
         assert_eq!(QueryType::count_types(), 1);
 
         let mut all_types = vec![];
-        QueryType::for_each_type(|t, m| all_types.push((t, m)));
+        QueryType::for_each_type(|t, _, m| all_types.push((t, m)));
         assert_eq!(all_types.as_slice(), &[(TypeId::of::<u32>(), false)]);
     }
 
@@ -42,18 +51,19 @@ mod tests {
         let reference = Some(&mut value);
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
-        let mut tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<Option<&mut u32>>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         let read_value: Option<u32> = tracked.as_deref().cloned();
         assert_eq!(read_value, Some(72));
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), false);
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
 
         if let Some(tracked_value) = &mut tracked {
             **tracked_value = 69;
         }
         let read_value: Option<u32> = tracked.as_deref().cloned();
         assert_eq!(read_value, Some(69));
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), true);
+        assert!(changes.is_changed(TypeId::of::<u32>()));
 
         assert_eq!(value, 69);
     }
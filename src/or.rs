@@ -0,0 +1,87 @@
+use crate::{ChangeSlot, TrackableRef};
+use core::any::TypeId;
+use hecs::{Entity, Or};
+
+impl<'a, L, R> TrackableRef<'a> for Or<L, R>
+where
+    L: TrackableRef<'a>,
+    R: TrackableRef<'a>,
+{
+    type Tracked = Or<<L as TrackableRef<'a>>::Tracked, <R as TrackableRef<'a>>::Tracked>;
+    type Slots = (
+        <L as TrackableRef<'a>>::Slots,
+        <R as TrackableRef<'a>>::Slots,
+    );
+
+    fn count_types() -> usize {
+        <L as TrackableRef>::count_types() + <R as TrackableRef>::count_types()
+    }
+
+    fn for_each_type(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        <L as TrackableRef>::for_each_type(&mut f);
+        <R as TrackableRef>::for_each_type(&mut f);
+    }
+
+    fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots {
+        let (left, right) = slots.split_at(<L as TrackableRef>::count_types());
+        (
+            <L as TrackableRef>::resolve_slots(left),
+            <R as TrackableRef>::resolve_slots(right),
+        )
+    }
+
+    #[inline]
+    fn into_tracked_with(self, entity: Entity, (left, right): Self::Slots) -> Self::Tracked {
+        self.map(
+            |value| value.into_tracked_with(entity, left),
+            |value| value.into_tracked_with(entity, right),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Changes, TrackableQuery, TrackableRef};
+    use core::any::TypeId;
+    use hecs::{Or, World};
+
+    #[test]
+    fn tracked_or_metadata() {
+        type QueryType<'a> = Or<&'a u32, &'a mut i32>;
+
+        assert_eq!(QueryType::count_types(), 2);
+
+        let mut all_types = vec![];
+        QueryType::for_each_type(|t, _, m| all_types.push((t, m)));
+        assert_eq!(
+            all_types.as_slice(),
+            &[(TypeId::of::<u32>(), false), (TypeId::of::<i32>(), true)]
+        );
+    }
+
+    #[test]
+    fn tracked_or_query() {
+        let mut world = World::new();
+        let a = world.spawn((1u32,));
+        let b = world.spawn((2i32,));
+        let c = world.spawn((3u32, 4i32));
+
+        let changes = Changes::new_for::<Or<&mut u32, &mut i32>>().with_entities();
+        for (entity, value) in Or::<&mut u32, &mut i32>::track(&changes)
+            .query(&world)
+            .iter()
+        {
+            match value {
+                Or::Left(mut left) => *left += 1,
+                Or::Right(right) => assert_eq!(right.entity(), entity),
+                Or::Both(_, mut right) => *right += 1,
+            }
+        }
+
+        assert_eq!(changes.changed_entities::<u32>(), vec![a]);
+        assert_eq!(changes.changed_entities::<i32>(), vec![c]);
+        assert!(!changes.is_entity_changed(TypeId::of::<i32>(), b));
+        assert_eq!(*world.get::<u32>(a).unwrap(), 2);
+        assert_eq!(*world.get::<i32>(c).unwrap(), 5);
+    }
+}
@@ -1,9 +1,14 @@
+#[allow(clippy::module_inception)]
 mod query;
-use crate::{Changes, TrackableRef};
+mod query_mut;
+mod query_one;
+use crate::{ChangeSlots, Changes, NotReserved, TrackableRef};
 use core::marker::PhantomData;
-use hecs::{Query, QueryItem, World};
+use hecs::{Entity, NoSuchEntity, Query, QueryItem, World};
 
 pub use query::{TrackedQueryBorrow, TrackedQueryIter};
+pub use query_mut::TrackedQueryMut;
+pub use query_one::TrackedQueryOne;
 
 pub trait TrackableQuery
 where
@@ -15,15 +20,24 @@ where
     {
         TrackedQueryBuilder::<'a, Self>::new(changes)
     }
+
+    /// Same as [`TrackableQuery::track`] but fails if any borrowed type is not reserved in `changes`.
+    fn try_track<'a>(changes: &'a Changes) -> Result<TrackedQueryBuilder<'a, Self>, NotReserved>
+    where
+        QueryItem<'a, Self>: TrackableRef<'a>,
+    {
+        changes.check_reserved::<QueryItem<'a, Self>>()?;
+        Ok(TrackedQueryBuilder::<'a, Self>::new(changes))
+    }
 }
 
-impl<'a, Q> TrackableQuery for Q where Q: 'a + Query {}
+impl<Q> TrackableQuery for Q where Q: Query {}
 
 pub struct TrackedQueryBuilder<'a, Q>
 where
     Q: 'a + Query,
 {
-    changes: &'a Changes,
+    slots: ChangeSlots<'a>,
     phantom: PhantomData<&'a Q>,
 }
 
@@ -31,19 +45,46 @@ impl<'a, Q> TrackedQueryBuilder<'a, Q>
 where
     Q: 'a + Query,
 {
-    fn new(changes: &'a Changes) -> Self {
+    fn new(changes: &'a Changes) -> Self
+    where
+        QueryItem<'a, Q>: TrackableRef<'a>,
+    {
         Self {
-            changes,
+            slots: changes.slots_for::<QueryItem<'a, Q>>(),
             phantom: PhantomData,
         }
     }
 
-    pub fn query<'w>(&self, world: &'w World) -> TrackedQueryBorrow<'w, Q>
+    pub fn query<'w>(&'w self, world: &'w World) -> TrackedQueryBorrow<'w, Q>
     where
         'a: 'w,
         QueryItem<'w, Q>: TrackableRef<'w>,
     {
-        TrackedQueryBorrow::new(world.query::<Q>(), self.changes)
+        TrackedQueryBorrow::with_slots(world.query::<Q>(), self.slots.borrowed())
+    }
+
+    /// Query the uniquely borrowed `world` without the runtime borrow checking, see [`World::query_mut`].
+    pub fn query_mut<'w>(&'w self, world: &'w mut World) -> TrackedQueryMut<'w, Q>
+    where
+        'a: 'w,
+    {
+        TrackedQueryMut::with_slots(world.query_mut::<Q>(), &self.slots)
+    }
+
+    /// Prepare the tracked query of a single `entity`, see [`World::query_one`].
+    pub fn query_one<'w>(
+        &'w self,
+        world: &'w World,
+        entity: Entity,
+    ) -> Result<TrackedQueryOne<'w, Q>, NoSuchEntity>
+    where
+        'a: 'w,
+    {
+        Ok(TrackedQueryOne::with_slots(
+            world.query_one::<Q>(entity)?,
+            entity,
+            self.slots.borrowed(),
+        ))
     }
 }
 
@@ -78,4 +119,116 @@ mod tests {
         assert_eq!(changes.len(), 1);
         assert!(changes.contains(&TypeId::of::<i32>()));
     }
+
+    #[test]
+    fn query_changed_entities() {
+        let mut world = World::default();
+        let a = world.spawn((0i32, 1u32));
+        let b = world.spawn((2i32, 3u32));
+
+        let changes = Changes::new_for::<(&i32, &u32)>().with_entities();
+
+        <(&'static mut i32, &'static u32)>::track(&changes)
+            .query(&world)
+            .iter()
+            .for_each(|(_, (mut a, b))| {
+                if *b > 2 {
+                    *a = *b as i32;
+                }
+            });
+
+        assert_eq!(changes.changed_entities::<i32>(), vec![b]);
+        assert!(!changes.is_entity_changed(TypeId::of::<i32>(), a));
+        assert!(changes.changed_entities::<u32>().is_empty());
+    }
+
+    #[test]
+    fn try_track() {
+        let mut world = World::default();
+        world.spawn((0i32, 1u32, 2u64));
+
+        let changes = Changes::new_for::<(&i32, &u32)>();
+
+        let error = <(
+            &'static mut i32,
+            &'static mut u32,
+            &'static mut u64,
+            &'static i8,
+        )>::try_track(&changes)
+        .err()
+        .unwrap();
+        assert_eq!(error.type_names(), &["u64", "i8"]);
+
+        assert!(<(&'static mut i32, &'static u32)>::try_track(&changes).is_ok());
+    }
+
+    #[test]
+    fn auto_reserve() {
+        let mut world = World::default();
+        world.spawn((0i32, 1u32));
+
+        let changes = Changes::new().with_auto_reserve();
+
+        <(&'static mut i32, &'static u32)>::try_track(&changes)
+            .unwrap()
+            .query(&world)
+            .iter()
+            .for_each(|(_, (mut a, b))| *a = *b as i32);
+
+        assert!(changes.is_changed(TypeId::of::<i32>()));
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+        assert_eq!(changes.iter().count(), 2);
+    }
+
+    #[test]
+    fn query_one() {
+        let mut world = World::default();
+        let a = world.spawn((0i32, 1u32));
+        let b = world.spawn((2i32,));
+
+        let changes = Changes::new_for::<(&i32, &u32)>().with_entities();
+        let query = <(&'static mut i32, &'static u32)>::track(&changes);
+
+        let mut one = query.query_one(&world, a).unwrap();
+        if let Some((mut a, b)) = one.get() {
+            *a = *b as i32;
+        }
+        drop(one);
+        assert!(query.query_one(&world, b).unwrap().get().is_none());
+        assert!(query
+            .query_one(&world, a)
+            .unwrap()
+            .with::<i64>()
+            .get()
+            .is_none());
+
+        assert_eq!(changes.changed_entities::<i32>(), vec![a]);
+        assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+
+        world.despawn(b).unwrap();
+        assert!(query.query_one(&world, b).is_err());
+    }
+
+    #[test]
+    fn query_mut() {
+        let mut world = World::default();
+        let a = world.spawn((0i32, 1u32));
+        let b = world.spawn((2i32, 3u32, 'b'));
+
+        let changes = Changes::new_for::<(&i32, &u32)>().with_entities();
+        let query = <(&'static mut i32, &'static u32)>::track(&changes);
+
+        for (_, (mut a, b)) in query.query_mut(&mut world) {
+            *a = *b as i32;
+        }
+        assert_eq!(changes.changed_entities::<i32>(), vec![a, b]);
+
+        changes.take_changed();
+        for (_, (mut a, _)) in query.query_mut(&mut world).without::<char>() {
+            *a += 1;
+        }
+        assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+        assert_eq!(*world.get::<i32>(b).unwrap(), 3);
+        assert!(changes.is_changed(TypeId::of::<i32>()));
+    }
 }
@@ -1,28 +1,36 @@
-use crate::{Changes, TrackableRef};
+use crate::{ChangeSlot, ChangeSlots, Changes, TrackableRef};
 use core::iter::{IntoIterator, Iterator};
 use hecs::{Entity, Query, QueryBorrow, QueryItem, QueryIter};
 
 pub struct TrackedQueryBorrow<'w, Q>
 where
     Q: Query,
-    QueryItem<'w, Q>: TrackableRef<'w>,
 {
     inner: QueryBorrow<'w, Q>,
-    changes: &'w Changes,
+    slots: ChangeSlots<'w>,
 }
 
 impl<'w, Q> TrackedQueryBorrow<'w, Q>
 where
     Q: Query,
-    QueryItem<'w, Q>: TrackableRef<'w>,
 {
-    pub fn new(inner: QueryBorrow<'w, Q>, changes: &'w Changes) -> Self {
-        Self { inner, changes }
+    pub fn new(inner: QueryBorrow<'w, Q>, changes: &'w Changes) -> Self
+    where
+        QueryItem<'w, Q>: TrackableRef<'w>,
+    {
+        Self::with_slots(inner, changes.slots_for::<QueryItem<'w, Q>>())
+    }
+
+    pub(crate) fn with_slots(inner: QueryBorrow<'w, Q>, slots: ChangeSlots<'w>) -> Self {
+        Self { inner, slots }
     }
 
     // The lifetime narrowing here is required for soundness.
-    pub fn iter(&mut self) -> TrackedQueryIter<'_, Q> {
-        TrackedQueryIter::new(self.inner.iter(), self.changes)
+    pub fn iter<'q>(&'q mut self) -> TrackedQueryIter<'q, Q>
+    where
+        QueryItem<'q, Q>: TrackableRef<'q>,
+    {
+        TrackedQueryIter::new(self.inner.iter(), &self.slots)
     }
 }
 
@@ -42,17 +50,23 @@ where
 pub struct TrackedQueryIter<'q, Q>
 where
     Q: Query,
+    QueryItem<'q, Q>: TrackableRef<'q>,
 {
     inner: QueryIter<'q, Q>,
-    changes: &'q Changes,
+    slots: <QueryItem<'q, Q> as TrackableRef<'q>>::Slots,
 }
 
 impl<'q, Q> TrackedQueryIter<'q, Q>
 where
     Q: Query,
+    QueryItem<'q, Q>: TrackableRef<'q>,
 {
-    fn new(inner: QueryIter<'q, Q>, changes: &'q Changes) -> Self {
-        Self { inner, changes }
+    /// Resolve `slots` once for all items of the query.
+    pub(crate) fn new(inner: QueryIter<'q, Q>, slots: &'q [ChangeSlot<'q>]) -> Self {
+        Self {
+            inner,
+            slots: QueryItem::<'q, Q>::resolve_slots(slots),
+        }
     }
 }
 
@@ -63,11 +77,12 @@ where
 {
     type Item = (Entity, <QueryItem<'q, Q> as TrackableRef<'q>>::Tracked);
 
-    #[inline]
+    // Forced, otherwise the items are returned through memory once the query has several call sites.
+    #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
         self.inner
             .next()
-            .map(|(entity, components)| (entity, components.into_tracked(self.changes)))
+            .map(|(entity, components)| (entity, components.into_tracked_with(entity, self.slots)))
     }
 
     #[inline]
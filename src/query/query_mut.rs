@@ -0,0 +1,45 @@
+use super::TrackedQueryIter;
+use crate::{ChangeSlot, TrackableRef};
+use hecs::{Component, Entity, Query, QueryItem, QueryMut, With, Without};
+
+/// Tracked counterpart of [`hecs::QueryMut`] without the runtime borrow checking,
+/// see [`crate::TrackedQueryBuilder::query_mut`].
+pub struct TrackedQueryMut<'q, Q>
+where
+    Q: Query,
+{
+    inner: QueryMut<'q, Q>,
+    slots: &'q [ChangeSlot<'q>],
+}
+
+impl<'q, Q> TrackedQueryMut<'q, Q>
+where
+    Q: Query,
+{
+    pub(crate) fn with_slots(inner: QueryMut<'q, Q>, slots: &'q [ChangeSlot<'q>]) -> Self {
+        Self { inner, slots }
+    }
+
+    /// Transform the query into one that requires a certain component without borrowing it.
+    pub fn with<T: Component>(self) -> TrackedQueryMut<'q, With<T, Q>> {
+        TrackedQueryMut::with_slots(self.inner.with::<T>(), self.slots)
+    }
+
+    /// Transform the query into one that skips entities having a certain component.
+    pub fn without<T: Component>(self) -> TrackedQueryMut<'q, Without<T, Q>> {
+        TrackedQueryMut::with_slots(self.inner.without::<T>(), self.slots)
+    }
+}
+
+impl<'q, Q> IntoIterator for TrackedQueryMut<'q, Q>
+where
+    Q: Query,
+    QueryItem<'q, Q>: TrackableRef<'q>,
+{
+    type IntoIter = TrackedQueryIter<'q, Q>;
+    type Item = (Entity, <QueryItem<'q, Q> as TrackableRef<'q>>::Tracked);
+
+    fn into_iter(self) -> Self::IntoIter {
+        TrackedQueryIter::new(self.inner.into_iter(), self.slots)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{ChangeSlots, TrackableRef};
+use hecs::{Component, Entity, Query, QueryItem, QueryOne, With, Without};
+
+/// Tracked counterpart of [`hecs::QueryOne`], see [`crate::TrackedQueryBuilder::query_one`].
+pub struct TrackedQueryOne<'w, Q>
+where
+    Q: Query,
+{
+    inner: QueryOne<'w, Q>,
+    entity: Entity,
+    slots: ChangeSlots<'w>,
+}
+
+impl<'w, Q> TrackedQueryOne<'w, Q>
+where
+    Q: Query,
+{
+    pub(crate) fn with_slots(
+        inner: QueryOne<'w, Q>,
+        entity: Entity,
+        slots: ChangeSlots<'w>,
+    ) -> Self {
+        Self {
+            inner,
+            entity,
+            slots,
+        }
+    }
+
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Get the tracked query result, or `None` if the entity does not satisfy the query.
+    ///
+    /// Panics if called more than once or if the borrow clashes with another one, same as [`QueryOne::get`].
+    // The lifetime narrowing here is required for soundness.
+    pub fn get<'q>(&'q mut self) -> Option<<QueryItem<'q, Q> as TrackableRef<'q>>::Tracked>
+    where
+        QueryItem<'q, Q>: TrackableRef<'q>,
+    {
+        let entity = self.entity;
+        let slots = &self.slots;
+        self.inner
+            .get()
+            .map(|components| components.into_tracked(entity, slots))
+    }
+
+    /// Transform the query into one that requires a certain component without borrowing it.
+    pub fn with<T: Component>(self) -> TrackedQueryOne<'w, With<T, Q>> {
+        TrackedQueryOne::with_slots(self.inner.with::<T>(), self.entity, self.slots)
+    }
+
+    /// Transform the query into one that skips the entity having a certain component.
+    pub fn without<T: Component>(self) -> TrackedQueryOne<'w, Without<T, Q>> {
+        TrackedQueryOne::with_slots(self.inner.without::<T>(), self.entity, self.slots)
+    }
+}
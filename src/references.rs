@@ -1,12 +1,18 @@
-use crate::{Changes, TrackableRef};
-use core::any::TypeId;
+use crate::{
+    ChangeSlot, DiffMut, HashDiffMut, Journal, JournalMut, RecordMut, TrackableRef, ValueLog,
+};
+use core::any::{type_name, TypeId};
+use core::hash::Hash;
 use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+use hecs::{Component, Entity};
 
 impl<'a, T> TrackableRef<'a> for &'a T
 where
     T: 'static,
 {
     type Tracked = TrackedRef<'a, T>;
+    type Slots = &'a ChangeSlot<'a>;
 
     #[inline]
     fn count_types() -> usize {
@@ -14,13 +20,18 @@ where
     }
 
     #[inline]
-    fn for_each_type(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), false);
+    fn for_each_type(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
     }
 
     #[inline]
-    fn into_tracked(self, changes: &'a Changes) -> Self::Tracked {
-        TrackedRef::new(self, changes)
+    fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots {
+        &slots[0]
+    }
+
+    #[inline]
+    fn into_tracked_with(self, entity: Entity, slot: Self::Slots) -> Self::Tracked {
+        TrackedRef::new(self, entity, slot)
     }
 }
 
@@ -29,6 +40,7 @@ where
     T: 'static,
 {
     type Tracked = TrackedMut<'a, T>;
+    type Slots = &'a ChangeSlot<'a>;
 
     #[inline]
     fn count_types() -> usize {
@@ -36,13 +48,18 @@ where
     }
 
     #[inline]
-    fn for_each_type(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), true);
+    fn for_each_type(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), true);
+    }
+
+    #[inline]
+    fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots {
+        &slots[0]
     }
 
     #[inline]
-    fn into_tracked(self, changes: &'a Changes) -> Self::Tracked {
-        TrackedMut::new(self, changes)
+    fn into_tracked_with(self, entity: Entity, slot: Self::Slots) -> Self::Tracked {
+        TrackedMut::new(self, entity, slot)
     }
 }
 
@@ -51,7 +68,10 @@ where
     T: 'static,
 {
     value: &'a T,
-    changes: &'a Changes,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    /// Whether the entity is already flagged, the interior mutations only report the first change.
+    mutated: AtomicBool,
 }
 
 impl<'a, T> TrackedRef<'a, T>
@@ -59,12 +79,41 @@ where
     T: 'static,
 {
     #[inline]
-    fn new(value: &'a T, changes: &'a Changes) -> Self {
-        Self { value, changes }
+    fn new(value: &'a T, entity: Entity, slot: &'a ChangeSlot<'a>) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            mutated: AtomicBool::new(false),
+        }
+    }
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
     }
+    /// Flag the type as changed, which is rejected in the strict mode
+    /// unless the type is reserved as interior-mutable, see [`crate::Changes::with_strict`].
+    /// Only the first call is reported.
     #[inline]
     pub fn set_mutated(&self) {
-        self.changes.set_changed(TypeId::of::<T>())
+        if !self.mutated.swap(true, Ordering::Relaxed) {
+            self.slot.set_entity_changed(self.entity)
+        }
+    }
+
+    /// Make a tracked reference to a part of the value, which still flags the type of the original value.
+    ///
+    /// This is an associated function to not conflict with the methods of `T`, like [`std::cell::Ref::map`].
+    pub fn map<U>(this: Self, f: impl FnOnce(&T) -> &U) -> TrackedRef<'a, U>
+    where
+        U: 'static,
+    {
+        TrackedRef {
+            value: f(this.value),
+            entity: this.entity,
+            slot: this.slot,
+            mutated: this.mutated,
+        }
     }
 }
 
@@ -73,19 +122,144 @@ where
     T: 'static,
 {
     value: &'a mut T,
-    changes: &'a Changes,
+    entity: Entity,
+    slot: &'a ChangeSlot<'a>,
+    /// Whether the entity is already flagged, shared with the [`crate::ElementMut`] of collections.
+    mutated: AtomicBool,
+    /// Whether [`crate::Changes::WHOLE_VALUE`] is already recorded.
+    value_mutated: bool,
 }
 
 impl<'a, T> TrackedMut<'a, T>
 where
     T: 'static,
 {
-    fn new(value: &'a mut T, changes: &'a Changes) -> Self {
-        Self { value, changes }
+    #[inline]
+    fn new(value: &'a mut T, entity: Entity, slot: &'a ChangeSlot<'a>) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            mutated: AtomicBool::new(false),
+            value_mutated: false,
+        }
+    }
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
     }
+    /// Flag the type and entity, only the first call or mutable access is reported.
     #[inline]
     pub fn set_mutated(&self) {
-        self.changes.set_changed(TypeId::of::<T>())
+        if !self.mutated.swap(true, Ordering::Relaxed) {
+            self.slot.set_entity_changed(self.entity)
+        }
+    }
+
+    /// Make a tracked reference to a part of the value, which still flags the type of the original value
+    /// on the first mutable access.
+    ///
+    /// This is an associated function to not conflict with the methods of `T`, like [`std::cell::RefMut::map`].
+    pub fn map<U>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> TrackedMut<'a, U>
+    where
+        U: 'static,
+    {
+        TrackedMut {
+            value: f(this.value),
+            entity: this.entity,
+            slot: this.slot,
+            mutated: AtomicBool::new(this.mutated.into_inner()),
+            value_mutated: this.value_mutated,
+        }
+    }
+
+    /// Mutably borrow a field of the value, recording only that field as changed.
+    /// Used by the accessors generated with `#[derive(TrackedFields)]`.
+    pub fn field_mut<F>(
+        &mut self,
+        field: &'static str,
+        f: impl FnOnce(&mut T) -> &mut F,
+    ) -> &mut F {
+        self.slot
+            .set_field_changed_once(self.entity, field, self.mutated.get_mut());
+        f(self.value)
+    }
+
+    /// Only flag the change if the value differs from the original one when dropped.
+    /// Mutations made through `self` before this call are already flagged.
+    pub fn diff(self) -> DiffMut<'a, T>
+    where
+        T: PartialEq + Clone,
+    {
+        let (value, entity, slot, mutated) = self.into_parts();
+        DiffMut::new(value, entity, slot, mutated)
+    }
+
+    /// Only flag the change if the hash of the value differs from the original one when dropped.
+    /// Mutations made through `self` before this call are already flagged.
+    pub fn hash_diff(self) -> HashDiffMut<'a, T>
+    where
+        T: Hash,
+    {
+        let (value, entity, slot, mutated) = self.into_parts();
+        HashDiffMut::new(value, entity, slot, mutated)
+    }
+
+    /// Record the value before the first mutable access and the new value into `log` on drop.
+    ///
+    /// Mutations made through `self` before this call are not recorded,
+    /// the old value is the one at the time of the call.
+    pub fn record(self, log: &'a ValueLog<T>) -> RecordMut<'a, T>
+    where
+        T: Clone,
+    {
+        let (value, entity, slot, mutated) = self.into_parts();
+        RecordMut::new(value, entity, slot, log, mutated)
+    }
+
+    /// Record the value before the first mutable access into `journal` for undo.
+    ///
+    /// Mutations made through `self` before this call are not recorded, so they are not undone.
+    pub fn journal(self, journal: &'a Journal) -> JournalMut<'a, T>
+    where
+        T: Component + Clone,
+    {
+        let (value, entity, slot, mutated) = self.into_parts();
+        JournalMut::new(value, entity, slot, journal, mutated)
+    }
+
+    /// Continue the tracking of another reference to the same value, see [`crate::TrackedRefMut::tracked`].
+    pub(crate) fn with_state(
+        value: &'a mut T,
+        entity: Entity,
+        slot: &'a ChangeSlot<'a>,
+        mutated: bool,
+        value_mutated: bool,
+    ) -> Self {
+        Self {
+            value,
+            entity,
+            slot,
+            mutated: AtomicBool::new(mutated),
+            value_mutated,
+        }
+    }
+
+    pub(crate) fn into_state(self) -> (bool, bool) {
+        (self.mutated.into_inner(), self.value_mutated)
+    }
+
+    pub(crate) fn parts_mut(&mut self) -> (&mut T, Entity, &'a ChangeSlot<'a>, &AtomicBool) {
+        (self.value, self.entity, self.slot, &self.mutated)
+    }
+
+    pub(crate) fn into_parts(self) -> (&'a mut T, Entity, &'a ChangeSlot<'a>, bool) {
+        (
+            self.value,
+            self.entity,
+            self.slot,
+            self.mutated.into_inner(),
+        )
     }
 }
 
@@ -96,7 +270,8 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(format!("TrackedRef<{}>", std::any::type_name::<T>()).as_str())
             .field("value", &self.value)
-            .field("mutated", &self.changes.is_changed(TypeId::of::<T>()))
+            .field("entity", &self.entity)
+            .field("mutated", &self.slot.is_changed())
             .finish()
     }
 }
@@ -108,36 +283,46 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(format!("TrackedMut<{}>", std::any::type_name::<T>()).as_str())
             .field("value", &self.value)
-            .field("mutated", &self.changes.is_changed(TypeId::of::<T>()))
+            .field("entity", &self.entity)
+            .field("mutated", &self.slot.is_changed())
             .finish()
     }
 }
 
 impl<'a, T> Deref for TrackedRef<'a, T> {
     type Target = T;
+    #[inline]
     fn deref(&self) -> &Self::Target {
-        &*(self.value)
+        self.value
     }
 }
 
 impl<'a, T> Deref for TrackedMut<'a, T> {
     type Target = T;
+    #[inline]
     fn deref(&self) -> &Self::Target {
-        &*(self.value)
+        self.value
     }
 }
 
 impl<'a, T> DerefMut for TrackedMut<'a, T> {
+    #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.set_mutated();
+        // Only the first mutation is reported, so the change events are not repeated.
+        if !self.value_mutated {
+            self.value_mutated = true;
+            self.slot
+                .set_value_changed_once(self.entity, self.mutated.get_mut());
+        }
         &mut *(self.value)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Changes, TrackableRef};
+    use crate::{Changes, TrackableRef, TrackedMut, TrackedRef};
     use core::any::TypeId;
+    use hecs::{Entity, World};
 
     #[test]
     fn tracked_ref_metadata() {
@@ -146,7 +331,7 @@ mod tests {
         assert_eq!(QueryType::count_types(), 1);
 
         let mut all_types = vec![];
-        QueryType::for_each_type(|t, m| all_types.push((t, m)));
+        QueryType::for_each_type(|t, _, m| all_types.push((t, m)));
         assert_eq!(all_types.as_slice(), &[(TypeId::of::<u32>(), false)]);
     }
 
@@ -157,7 +342,7 @@ mod tests {
         assert_eq!(QueryType::count_types(), 1);
 
         let mut all_types = vec![];
-        QueryType::for_each_type(|t, m| all_types.push((t, m)));
+        QueryType::for_each_type(|t, _, m| all_types.push((t, m)));
         assert_eq!(all_types.as_slice(), &[(TypeId::of::<u32>(), true)]);
     }
 
@@ -167,11 +352,12 @@ mod tests {
         let reference = &value;
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
-        let tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<&u32>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         let read_value: u32 = *tracked;
         assert_eq!(read_value, 72);
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), false);
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
     }
 
     #[test]
@@ -180,10 +366,11 @@ mod tests {
         let reference = &value;
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
-        let tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<&u32>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         tracked.set_mutated();
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), true);
+        assert!(changes.is_changed(TypeId::of::<u32>()));
     }
 
     #[test]
@@ -192,10 +379,11 @@ mod tests {
         let reference = &mut value;
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
-        let tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<&mut u32>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         tracked.set_mutated();
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), true);
+        assert!(changes.is_changed(TypeId::of::<u32>()));
     }
 
     #[test]
@@ -204,17 +392,113 @@ mod tests {
         let reference = &mut value;
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
-        let mut tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<&mut u32>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         let read_value: u32 = *tracked;
         assert_eq!(read_value, 72);
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), false);
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
 
         *tracked = 69;
         let read_value: u32 = *tracked;
         assert_eq!(read_value, 69);
-        assert_eq!(changes.is_changed(TypeId::of::<u32>()), true);
+        assert!(changes.is_changed(TypeId::of::<u32>()));
 
         assert_eq!(value, 69);
     }
+
+    #[test]
+    fn tracked_mut_events() {
+        let mut value = 72u32;
+        let reference = &mut value;
+        let changes = Changes::new_for::<&u32>().with_events();
+        let slots = changes.slots_for::<&mut u32>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots);
+
+        tracked.set_mutated();
+        *tracked = 69;
+        *tracked += 1;
+
+        let events: Vec<_> = changes.drain_events().collect();
+        assert_eq!(events, vec![(Entity::DANGLING, TypeId::of::<u32>())]);
+        assert_eq!(value, 70);
+    }
+
+    #[test]
+    fn tracked_mut_field() {
+        let mut value = (1u32, 2u32);
+        let reference = &mut value;
+        let changes = Changes::new_for::<&(u32, u32)>().with_fields();
+        let slots = changes.slots_for::<&mut (u32, u32)>();
+        let mut tracked = reference.into_tracked(Entity::DANGLING, &slots);
+
+        *tracked.field_mut("1", |v| &mut v.1) = 3;
+
+        assert!(changes.is_changed(TypeId::of::<(u32, u32)>()));
+        assert_eq!(changes.changed_fields::<(u32, u32)>(), vec!["1"]);
+        assert_eq!(value, (1, 3));
+    }
+
+    #[test]
+    fn tracked_mut_whole_value() {
+        let mut world = World::new();
+        let a = world.spawn(((1u32, 2u32),));
+        let b = world.spawn(((3u32, 4u32),));
+
+        let changes = Changes::new_for::<&(u32, u32)>()
+            .with_entities()
+            .with_fields();
+        let slots = changes.slots_for::<&mut (u32, u32)>();
+        for (entity, value) in world.query_mut::<&mut (u32, u32)>() {
+            let mut tracked = value.into_tracked(entity, &slots);
+            *tracked.field_mut("0", |v| &mut v.0) += 1;
+            if entity == b {
+                *tracked = (0, 0);
+            }
+        }
+
+        assert_eq!(
+            changes.changed_fields::<(u32, u32)>(),
+            vec![Changes::WHOLE_VALUE, "0"]
+        );
+        assert_eq!(changes.changed_entity_fields::<(u32, u32)>(a), vec!["0"]);
+        assert_eq!(
+            changes.changed_entity_fields::<(u32, u32)>(b),
+            vec![Changes::WHOLE_VALUE, "0"]
+        );
+    }
+
+    #[test]
+    fn tracked_mut_map() {
+        let mut value = (1u32, 2u64);
+        let reference = &mut value;
+        let changes = Changes::new_for::<&(u32, u64)>().with_entities();
+        let slots = changes.slots_for::<&mut (u32, u64)>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
+
+        let mut second = TrackedMut::map(tracked, |v| &mut v.1);
+        assert_eq!(*second, 2);
+        assert!(!changes.is_changed(TypeId::of::<(u32, u64)>()));
+
+        *second = 3;
+        assert!(changes.is_changed(TypeId::of::<(u32, u64)>()));
+        assert!(!changes.is_changed(TypeId::of::<u64>()));
+        assert!(changes.is_entity_changed(TypeId::of::<(u32, u64)>(), Entity::DANGLING));
+        assert_eq!(value, (1, 3));
+    }
+
+    #[test]
+    fn tracked_ref_map() {
+        let value = (1u32, 2u64);
+        let reference = &value;
+        let changes = Changes::new_for::<&(u32, u64)>();
+        let slots = changes.slots_for::<&(u32, u64)>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
+
+        let second = TrackedRef::map(tracked, |v| &v.1);
+        assert_eq!(*second, 2);
+
+        second.set_mutated();
+        assert!(changes.is_changed(TypeId::of::<(u32, u64)>()));
+    }
 }
@@ -0,0 +1,84 @@
+use crate::{ChangeSlot, TrackableRef};
+use core::any::TypeId;
+use hecs::Entity;
+
+/// Item of [`hecs::Satisfies`], passed through as is.
+///
+/// Items of [`hecs::With`] and [`hecs::Without`] are the items of the inner queries,
+/// and the entity is yielded by the query iterator, so they don't need their own implementations.
+impl<'a> TrackableRef<'a> for bool {
+    type Tracked = bool;
+    type Slots = ();
+
+    #[inline]
+    fn count_types() -> usize {
+        0
+    }
+
+    #[inline]
+    fn for_each_type(_f: impl FnMut(TypeId, &'static str, bool)) {}
+
+    #[inline]
+    fn resolve_slots(_slots: &'a [ChangeSlot<'a>]) -> Self::Slots {}
+
+    #[inline]
+    fn into_tracked_with(self, _entity: Entity, _slots: Self::Slots) -> Self::Tracked {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Changes, TrackableQuery, TrackableRef};
+    use hecs::{Satisfies, With, Without, World};
+
+    #[test]
+    fn tracked_satisfies_metadata() {
+        type QueryType<'a> = (&'a mut u32, bool);
+
+        assert_eq!(QueryType::count_types(), 1);
+
+        let mut all_types = vec![];
+        QueryType::for_each_type(|_, n, m| all_types.push((n, m)));
+        assert_eq!(all_types.as_slice(), &[("u32", true)]);
+    }
+
+    #[test]
+    fn tracked_satisfies_query() {
+        let mut world = World::new();
+        let a = world.spawn((1u32,));
+        let b = world.spawn((2u32, 'f'));
+
+        let changes = Changes::new_for::<&u32>().with_entities();
+        type Frozen<'a> = Satisfies<&'a char>;
+        for (_, (mut value, frozen)) in <(&mut u32, Frozen)>::track(&changes).query(&world).iter() {
+            if !frozen {
+                *value += 1;
+            }
+        }
+
+        assert_eq!(changes.changed_entities::<u32>(), vec![a]);
+        assert_eq!(*world.get::<u32>(b).unwrap(), 2);
+    }
+
+    #[test]
+    fn tracked_with_query() {
+        let mut world = World::new();
+        let a = world.spawn((1u32,));
+        let b = world.spawn((2u32, 'p'));
+
+        let changes = Changes::new_for::<&u32>().with_entities();
+        for (_, mut value) in <With<char, &mut u32>>::track(&changes).query(&world).iter() {
+            *value += 1;
+        }
+        assert_eq!(changes.changed_entities::<u32>(), vec![b]);
+
+        for (_, mut value) in <Without<char, &mut u32>>::track(&changes)
+            .query(&world)
+            .iter()
+        {
+            *value += 1;
+        }
+        assert_eq!(changes.changed_entities::<u32>(), vec![a, b]);
+    }
+}
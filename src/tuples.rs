@@ -1,8 +1,9 @@
-use crate::{Changes, TrackableRef};
+use crate::{ChangeSlot, ComponentSet, TrackableRef};
 use core::any::TypeId;
+use hecs::Entity;
 
 macro_rules! tracked_tuple_impl {
-    ($($name: ident), *) => {
+    ($(($name: ident, $slot: ident)), *) => {
         impl<'a, $($name),*> TrackableRef<'a> for ($($name,)*)
         where
             $(
@@ -14,6 +15,11 @@ macro_rules! tracked_tuple_impl {
                     <$name as TrackableRef<'a>>::Tracked,
                 )*
             );
+            type Slots = (
+                $(
+                    <$name as TrackableRef<'a>>::Slots,
+                )*
+            );
 
 
             #[allow(unused_mut)]
@@ -25,34 +31,71 @@ macro_rules! tracked_tuple_impl {
             }
 
             #[allow(unused_variables, unused_mut)]
-            fn for_each_type(mut f: impl FnMut(TypeId, bool)) {
+            fn for_each_type(mut f: impl FnMut(TypeId, &'static str, bool)) {
                 $(
-                    <$name as TrackableRef<'a>>::for_each_type(|t, m| f(t,m));
+                    <$name as TrackableRef<'a>>::for_each_type(|t, n, m| f(t, n, m));
                 )*
             }
 
-            #[allow(unused_variables)]
-            fn into_tracked(self, changes: &'a Changes) -> Self::Tracked {
-                #[allow(non_snake_case)]
-                let ($($name,)*) = self;
+            #[allow(unused_variables, unused_mut, unused_assignments, clippy::unused_unit)]
+            fn resolve_slots(slots: &'a [ChangeSlot<'a>]) -> Self::Slots {
+                let mut slots = slots;
                 (
                     $(
-                        $name.into_tracked(changes),
+                        {
+                            let (head, tail) = slots.split_at(<$name as TrackableRef<'a>>::count_types());
+                            slots = tail;
+                            <$name as TrackableRef<'a>>::resolve_slots(head)
+                        },
                     )*
                 )
             }
+
+            #[inline]
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn into_tracked_with(self, entity: Entity, slots: Self::Slots) -> Self::Tracked {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                let ($($slot,)*) = slots;
+                ($($name.into_tracked_with(entity, $slot),)*)
+            }
         }
     }
 }
 
+macro_rules! component_set_impl {
+    ($($name: ident), *) => {
+        impl<$($name),*> ComponentSet for ($($name,)*)
+        where
+            $(
+                $name: 'static,
+            )*
+        {
+            #[allow(unused_variables, unused_mut)]
+            fn for_each_type(mut f: impl FnMut(TypeId)) {
+                $(
+                    f(TypeId::of::<$name>());
+                )*
+            }
+        }
+    }
+}
+
+#[rustfmt::skip]
+smaller_tuples_too!(
+    tracked_tuple_impl,
+    (O, o), (N, n), (M, m), (L, l), (K, k), (J, j), (I, i), (H, h),
+    (G, g), (F, f), (E, e), (D, d), (C, c), (B, b), (A, a)
+);
 #[rustfmt::skip]
-smaller_tuples_too!(tracked_tuple_impl, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
+smaller_tuples_too!(component_set_impl, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
 // smaller_tuples_too!(tracked_tuple_impl, B, A);
 
 #[cfg(test)]
 mod tests {
     use crate::{Changes, TrackableRef};
     use core::any::TypeId;
+    use hecs::Entity;
 
     #[test]
     fn tracked_tuple_metadata() {
@@ -61,7 +104,7 @@ mod tests {
         assert_eq!(QueryType::count_types(), 4);
 
         let mut all_types = vec![];
-        QueryType::for_each_type(|t, m| all_types.push((t, m)));
+        QueryType::for_each_type(|t, _, m| all_types.push((t, m)));
         all_types.sort();
         let mut expected_types = [
             (TypeId::of::<bool>(), true),
@@ -81,11 +124,12 @@ mod tests {
         let mut changes = Changes::new();
         changes.reserve(TypeId::of::<u32>());
         changes.reserve(TypeId::of::<bool>());
-        let tracked = reference.into_tracked(&changes);
+        let slots = changes.slots_for::<(Option<&mut bool>, &mut u32)>();
+        let tracked = reference.into_tracked(Entity::DANGLING, &slots);
 
         let (mut a, mut b) = tracked;
         a.as_ref()
-            .map_or_else(|| unreachable!("a is None"), |a| assert_eq!(**a, false));
+            .map_or_else(|| unreachable!("a is None"), |a| assert!(!**a));
         assert_eq!(*b, 0);
 
         let mut changed_types = vec![];
@@ -96,7 +140,9 @@ mod tests {
         changes.for_each_changed(|t| changed_types.push(t));
         assert_eq!(changed_types.as_slice(), &[TypeId::of::<u32>()]);
 
-        a.as_mut().map(|a| **a = true);
+        if let Some(a) = a.as_mut() {
+            **a = true;
+        }
         let mut changed_types = vec![];
         changes.for_each_changed(|t| changed_types.push(t));
         let expected_changed_types = &mut [TypeId::of::<u32>(), TypeId::of::<bool>()];
@@ -0,0 +1,206 @@
+use crate::{Changes, TrackedEntityRef, TrackedRefMut};
+use core::any::TypeId;
+use core::ops::Deref;
+use hecs::{Bundle, Component, ComponentError, DynamicBundle, Entity, NoSuchEntity, World};
+
+/// Wrapper around [`World`] recording structural changes into [`Changes`].
+///
+/// Spawned and inserted components are recorded as added,
+/// removed and despawned ones as removed, see [`Changes::for_each_added`] and [`Changes::for_each_removed`].
+/// Components replaced by [`TrackedWorld::insert`] are recorded as changed.
+/// Structural changes of the types that are not reserved in [`Changes`] are ignored.
+pub struct TrackedWorld<'a> {
+    world: &'a mut World,
+    changes: &'a Changes,
+}
+
+impl<'a> TrackedWorld<'a> {
+    pub fn new(world: &'a mut World, changes: &'a Changes) -> Self {
+        Self { world, changes }
+    }
+
+    pub fn changes(&self) -> &'a Changes {
+        self.changes
+    }
+
+    /// Uniquely borrow the component of type `T` of `entity`, which is flagged on the first mutable access.
+    pub fn get_mut<T: Component>(
+        &self,
+        entity: Entity,
+    ) -> Result<TrackedRefMut<'_, T>, ComponentError> {
+        Ok(TrackedRefMut::new(
+            self.world.get_mut::<T>(entity)?,
+            entity,
+            self.changes,
+        ))
+    }
+
+    /// Access `entity` with the tracked unique borrows, see [`TrackedEntityRef`].
+    pub fn entity(&self, entity: Entity) -> Result<TrackedEntityRef<'_>, NoSuchEntity> {
+        Ok(TrackedEntityRef::new(
+            self.world.entity(entity)?,
+            self.changes,
+        ))
+    }
+
+    pub fn spawn(&mut self, components: impl DynamicBundle) -> Entity {
+        let entity = self.world.spawn(components);
+        self.component_types(entity)
+            .into_iter()
+            .for_each(|t| self.set_added(t, entity));
+        entity
+    }
+
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+    ) -> Result<(), NoSuchEntity> {
+        let existing = self.component_types(entity);
+        let inserted = components.with_ids(|ids| ids.to_vec());
+        self.world.insert(entity, components)?;
+        inserted.into_iter().for_each(|t| {
+            if existing.contains(&t) {
+                let slot = self.changes.slot(t);
+                if slot.is_reserved() {
+                    // Overwriting a component is a mutable access.
+                    slot.set_mutable();
+                    slot.set_value_changed(entity);
+                }
+            } else {
+                self.set_added(t, entity);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn insert_one(
+        &mut self,
+        entity: Entity,
+        component: impl Component,
+    ) -> Result<(), NoSuchEntity> {
+        self.insert(entity, (component,))
+    }
+
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        let removed = self.world.remove::<T>(entity)?;
+        T::with_static_ids(|ids| ids.iter().for_each(|t| self.set_removed(*t, entity)));
+        Ok(removed)
+    }
+
+    pub fn remove_one<T: Component>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        self.remove::<(T,)>(entity).map(|(x,)| x)
+    }
+
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        let removed = self.component_types(entity);
+        self.world.despawn(entity)?;
+        removed
+            .into_iter()
+            .for_each(|t| self.set_removed(t, entity));
+        Ok(())
+    }
+
+    fn component_types(&self, entity: Entity) -> Vec<TypeId> {
+        match self.world.entity(entity) {
+            Ok(entity) => entity.component_types().collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn set_added(&self, type_id: TypeId, entity: Entity) {
+        let slot = self.changes.slot(type_id);
+        if slot.is_reserved() {
+            slot.set_added(entity);
+        }
+    }
+
+    fn set_removed(&self, type_id: TypeId, entity: Entity) {
+        let slot = self.changes.slot(type_id);
+        if slot.is_reserved() {
+            slot.set_removed(entity);
+        }
+    }
+}
+
+impl<'a> Deref for TrackedWorld<'a> {
+    type Target = World;
+    fn deref(&self) -> &Self::Target {
+        self.world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackedWorld;
+    use crate::Changes;
+    use core::any::TypeId;
+    use hecs::World;
+
+    #[test]
+    fn spawn_and_despawn() {
+        let mut world = World::new();
+        let changes = Changes::new_for::<(&u32, &i32)>();
+        let mut tracked = TrackedWorld::new(&mut world, &changes);
+
+        let a = tracked.spawn((0u32, 1i32, 2u64));
+        let b = tracked.spawn((3u32,));
+        tracked.despawn(a).unwrap();
+
+        assert_eq!(changes.added_entities::<u32>(), vec![a, b]);
+        assert_eq!(changes.added_entities::<i32>(), vec![a]);
+        assert_eq!(changes.removed_entities::<u32>(), vec![a]);
+        assert_eq!(changes.removed_entities::<i32>(), vec![a]);
+        assert!(!changes.is_entity_removed(TypeId::of::<u32>(), b));
+        assert!(!changes.is_changed(TypeId::of::<u32>()));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut world = World::new();
+        let a = world.spawn((0u32,));
+        let changes = Changes::new_for::<(&u32, &i32)>().with_entities();
+        let mut tracked = TrackedWorld::new(&mut world, &changes);
+
+        tracked.insert(a, (1u32, 2i32)).unwrap();
+        assert!(changes.is_entity_changed(TypeId::of::<u32>(), a));
+        assert!(!changes.is_entity_added(TypeId::of::<u32>(), a));
+        assert!(changes.is_entity_added(TypeId::of::<i32>(), a));
+
+        assert_eq!(tracked.remove_one::<i32>(a), Ok(2));
+        assert!(changes.is_entity_removed(TypeId::of::<i32>(), a));
+        assert!(!changes.is_entity_removed(TypeId::of::<u32>(), a));
+
+        assert!(tracked.remove_one::<i32>(a).is_err());
+        assert_eq!(*tracked.get::<u32>(a).unwrap(), 1);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut world = World::new();
+        let changes = Changes::new_for::<&u32>().with_entities();
+        let mut tracked = TrackedWorld::new(&mut world, &changes);
+        let a = tracked.spawn((1u32,));
+        let b = tracked.spawn((2u32,));
+
+        *tracked.get_mut::<u32>(a).unwrap() += 1;
+        assert_eq!(*tracked.get_mut::<u32>(b).unwrap(), 2);
+        assert!(tracked.get_mut::<i32>(a).is_err());
+        *tracked.entity(b).unwrap().get_mut::<u32>().unwrap() += 1;
+
+        assert_eq!(changes.changed_entities::<u32>(), vec![a, b]);
+        assert_eq!(*world.get::<u32>(a).unwrap(), 2);
+    }
+
+    #[test]
+    fn get_mut_auto_reserved() {
+        let mut world = World::new();
+        let changes = Changes::new_for::<&u32>().with_auto_reserve();
+        let mut tracked = TrackedWorld::new(&mut world, &changes);
+        let a = tracked.spawn((1u32, 2i32));
+
+        *tracked.get_mut::<i32>(a).unwrap() += 1;
+        assert!(changes.is_changed(TypeId::of::<i32>()));
+        assert_eq!(*world.get::<i32>(a).unwrap(), 3);
+    }
+}
@@ -0,0 +1,126 @@
+use core::any::TypeId;
+use hecs::Entity;
+use hecs::{Query, World};
+use hecs_query_tracker::{Changes, TrackableQuery, TrackableRef, TrackedFields};
+
+#[derive(Debug, Default, PartialEq, TrackedFields)]
+pub struct Position {
+    x: f32,
+    y: f32,
+    r#type: u8,
+}
+
+#[test]
+fn tracked_fields() {
+    assert_eq!(Position::fields(), &["x", "y", "type"]);
+
+    let mut world = World::new();
+    let a = world.spawn((Position::default(),));
+    let b = world.spawn((Position::default(),));
+
+    let changes = Changes::new_for::<&Position>()
+        .with_entities()
+        .with_fields();
+    <&mut Position>::track(&changes)
+        .query(&world)
+        .iter()
+        .for_each(|(entity, mut position)| {
+            if entity == b {
+                *position.y_mut() = 2.0;
+                *position.type_mut() = 1;
+            } else {
+                *position = Position {
+                    x: 1.0,
+                    ..Default::default()
+                };
+            }
+        });
+
+    assert!(changes.is_changed(TypeId::of::<Position>()));
+    assert_eq!(
+        changes.changed_fields::<Position>(),
+        vec![Changes::WHOLE_VALUE, "type", "y"]
+    );
+    assert_eq!(
+        changes.changed_entity_fields::<Position>(a),
+        vec![Changes::WHOLE_VALUE]
+    );
+    assert_eq!(
+        changes.changed_entity_fields::<Position>(b),
+        vec!["type", "y"]
+    );
+    assert_eq!(
+        *world.get::<Position>(b).unwrap(),
+        Position {
+            x: 0.0,
+            y: 2.0,
+            r#type: 1
+        }
+    );
+}
+
+#[derive(Query, TrackableRef)]
+struct Movement<'a> {
+    position: &'a mut Position,
+    velocity: Option<&'a f32>,
+}
+
+#[derive(TrackableRef)]
+struct Health<'a>(&'a u32, &'a mut i32);
+
+#[test]
+fn tracked_query_struct() {
+    let mut all_types = vec![];
+    Movement::for_each_type(|_, n, m| all_types.push((n, m)));
+    assert_eq!(all_types, vec![("derive::Position", true), ("f32", false)]);
+
+    let mut position = Position::default();
+    let velocity = 2.0;
+    let query = Movement {
+        position: &mut position,
+        velocity: Some(&velocity),
+    };
+    let changes = Changes::new_for::<Movement>().with_entities();
+    let slots = changes.slots_for::<Movement>();
+    let mut tracked: TrackedMovement = query.into_tracked(Entity::DANGLING, &slots);
+
+    tracked.position.x += *tracked.velocity.unwrap();
+    assert_eq!(
+        changes.changed_entities::<Position>(),
+        vec![Entity::DANGLING]
+    );
+    assert!(!changes.is_changed(TypeId::of::<f32>()));
+    assert_eq!(position.x, 2.0);
+
+    let (a, mut b) = (1u32, 2i32);
+    let changes = Changes::new_for::<Health>();
+    let slots = changes.slots_for::<Health>();
+    let mut tracked = Health(&a, &mut b).into_tracked(Entity::DANGLING, &slots);
+    *tracked.1 += *tracked.0 as i32;
+    assert!(changes.is_changed(TypeId::of::<i32>()));
+    assert!(!changes.is_changed(TypeId::of::<u32>()));
+    assert_eq!(b, 3);
+}
+
+#[test]
+fn tracked_derived_query() {
+    let mut world = World::new();
+    let a = world.spawn((Position::default(), 2.0f32));
+    let b = world.spawn((Position::default(),));
+
+    let changes = Changes::new_for::<Movement>().with_entities().with_fields();
+    Movement::track(&changes)
+        .query(&world)
+        .iter()
+        .for_each(|(_, mut movement)| {
+            if let Some(velocity) = movement.velocity {
+                *movement.position.x_mut() += *velocity;
+            }
+        });
+
+    assert_eq!(changes.changed_entities::<Position>(), vec![a]);
+    assert_eq!(changes.changed_fields::<Position>(), vec!["x"]);
+    assert!(!changes.is_changed(TypeId::of::<f32>()));
+    assert_eq!(world.get::<Position>(a).unwrap().x, 2.0);
+    assert_eq!(world.get::<Position>(b).unwrap().x, 0.0);
+}